@@ -315,8 +315,7 @@ impl PyRobstrideActuator {
         self.rt.spawn(async move {
             let mut supervisor = self.supervisor.lock().await;
             if let Err(e) = supervisor.run(interval).await {
-                // Handle the error (you might want to log it or propagate it in a way that's usable by Python)
-                eprintln!("Error running supervisor: {}", e);
+                tracing::error!("Error running supervisor: {}", e);
             }
         });
 
@@ -338,6 +337,7 @@ impl From<PyRobstrideActuatorConfig> for robstride::ActuatorConfiguration {
             max_angle_change: config.max_angle_change.map(|v| v as f32),
             max_velocity: config.max_velocity.map(|v| v as f32),
             command_rate_hz: Some(100.0f32),
+            ..Default::default()
         }
     }
 }
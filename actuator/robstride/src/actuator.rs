@@ -5,6 +5,49 @@ use eyre::Result;
 use num_traits::{FromPrimitive, ToPrimitive};
 use serde::{Deserialize, Serialize};
 
+// Byte order of the frame payloads: the MIT fields of control and feedback
+// frames are big-endian u16s, while parameter indices, parameter values and
+// fault words are little-endian. Use these helpers rather than picking the
+// byte order per command.
+
+/// Reads a big-endian MIT field (control or feedback) at `offset`.
+pub fn get_mit_field_be(data: &[u8; 8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+/// Writes a big-endian MIT field (control or feedback) at `offset`.
+pub fn put_mit_field_be(data: &mut [u8; 8], offset: usize, value: u16) {
+    data[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+}
+
+/// Reads the little-endian parameter index from bytes 0-1.
+pub fn get_index(data: &[u8; 8]) -> u16 {
+    u16::from_le_bytes([data[0], data[1]])
+}
+
+/// Writes the little-endian parameter index to bytes 0-1.
+pub fn put_index(data: &mut [u8; 8], index: u16) {
+    data[0..2].copy_from_slice(&index.to_le_bytes());
+}
+
+/// Reads a little-endian 32-bit value (parameter value or fault word) at
+/// `offset`. Parameter values sit at offset 4; use `f32::from_bits` for
+/// float parameters.
+pub fn get_value_le(data: &[u8; 8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// Writes a little-endian 32-bit value (parameter value or fault word) at
+/// `offset`.
+pub fn put_value_le(data: &mut [u8; 8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
 pub fn normalize_value(
     value: f32,
     min: f32,
@@ -17,15 +60,16 @@ pub fn normalize_value(
         .clamp(out_range_min, out_range_max)
 }
 
+/// Maps `normalized` from `min..=max` back to `out_range_min..=out_range_max`,
+/// undoing a `normalize_value` call with the two ranges swapped.
 pub fn denormalize_value(
     normalized: f32,
     min: f32,
     max: f32,
-    in_range_min: f32,
-    in_range_max: f32,
+    out_range_min: f32,
+    out_range_max: f32,
 ) -> f32 {
-    let range = in_range_max - in_range_min;
-    ((normalized - in_range_min) * range / (in_range_max - in_range_min) + min).clamp(min, max)
+    normalize_value(normalized, min, max, out_range_min, out_range_max)
 }
 
 pub trait TypedCommandData: Send + Sync {
@@ -33,6 +77,19 @@ pub trait TypedCommandData: Send + Sync {
     fn from_control_command(cmd: ControlCommand) -> Self
     where
         Self: Sized;
+    fn target_angle_rad(&self) -> f32;
+    fn target_velocity_rads(&self) -> f32;
+    fn kp(&self) -> f32;
+    fn kd(&self) -> f32;
+    fn torque_nm(&self) -> f32;
+
+    fn target_angle_deg(&self) -> f32 {
+        self.target_angle_rad().to_degrees()
+    }
+
+    fn target_velocity_degs(&self) -> f32 {
+        self.target_velocity_rads().to_degrees()
+    }
 }
 
 pub trait TypedFeedbackData: Send + Sync {
@@ -42,6 +99,14 @@ pub trait TypedFeedbackData: Send + Sync {
     fn angle_rad(&self) -> f32;
     fn velocity_rads(&self) -> f32;
     fn torque_nm(&self) -> f32;
+
+    fn angle_deg(&self) -> f32 {
+        self.angle_rad().to_degrees()
+    }
+
+    fn velocity_degs(&self) -> f32 {
+        self.velocity_rads().to_degrees()
+    }
 }
 
 #[async_trait]
@@ -56,14 +121,27 @@ pub trait Actuator: Send + Sync + std::fmt::Debug {
 
     fn id(&self) -> u8;
     fn actuator_type(&self) -> ActuatorType;
+    fn host_id(&self) -> u8;
 
     async fn write_parameter(&self, cmd: WriteCommand) -> Result<()>;
     async fn read_parameter(&self, param_index: u16) -> Result<()>;
     async fn get_parameter_string_info(&self) -> Result<()>;
 
+    /// Writes a float parameter, e.g. an index from the model's parameter
+    /// table, with the actuator's host ID.
+    async fn set_parameter(&self, param_index: u16, value: f32) -> Result<()> {
+        self.write_parameter(WriteCommand {
+            host_id: self.host_id(),
+            parameter_index: param_index,
+            data: value,
+        })
+        .await
+    }
+
     async fn set_max_torque(&self, torque: f32) -> Result<()>;
     async fn set_max_velocity(&self, velocity: f32) -> Result<()>;
     async fn set_max_current(&self, current: f32) -> Result<()>;
+    async fn set_run_mode(&self, mode: RunMode) -> Result<()>;
 }
 
 #[derive(Debug, Clone)]
@@ -84,32 +162,37 @@ impl Command {
         }
     }
     // Convert from CAN packet format
-    pub fn from_can_packet(id: u32, mut data: Vec<u8>) -> Self {
+    // Fails on communication types this crate doesn't know, rather than
+    // misreading their payload as some other frame
+    pub fn from_can_packet(id: u32, payload: &[u8]) -> Result<Self, String> {
         // Extract fields from id
         let can_id = (id & 0x7F) as u8; // First 7 bits
         let data_2 = ((id >> 8) & 0xFFFF) as u16; // Bits 8-23
         let comm_type = ((id >> 24) & 0x1F) as u8; // Bits 24-28
 
         // Pad data to 8 bytes if needed TODO prepend vs append
-        data.resize(8, 0);
-        let data: [u8; 8] = data.try_into().unwrap();
+        let len = payload.len().min(8);
+        let mut data = [0u8; 8];
+        data[..len].copy_from_slice(&payload[..len]);
 
-        Command {
+        let communication_type = CommunicationType::from_u8(comm_type)
+            .ok_or_else(|| format!("Unknown communication type: {}", comm_type))?;
+
+        Ok(Command {
             data,
             can_id,
             data_2,
-            communication_type: CommunicationType::from_u8(comm_type)
-                .unwrap_or(CommunicationType::Control),
-        }
+            communication_type,
+        })
     }
 
     // Convert to CAN packet format
-    pub fn to_can_packet(&self) -> (u32, Vec<u8>) {
+    pub fn to_can_packet(&self) -> (u32, [u8; 8]) {
         let id: u32 = (self.can_id as u32)
             | ((self.data_2 as u32) << 8)
             | ((self.communication_type as u32) << 24);
 
-        (id, self.data.to_vec())
+        (id, self.data)
     }
 
     pub fn to_frame(&self) -> Result<Frame, String> {
@@ -121,6 +204,12 @@ impl Command {
                 Ok(Frame::Control(ControlCommand::from_command(self.clone())))
             }
             CommunicationType::Feedback => {
+                // Modes 0-2 are defined, reject the fourth value rather than
+                // guess at what the drive is doing
+                let mode_bits = (self.data_2 >> 14) & 0x03;
+                if MotorMode::from_u16(mode_bits).is_none() {
+                    return Err(format!("Unknown motor mode: {}", mode_bits));
+                }
                 Ok(Frame::Feedback(FeedbackFrame::from_command(self.clone())))
             }
             CommunicationType::Read => Ok(Frame::Read(ReadCommand::from_command(self.clone()))),
@@ -132,21 +221,10 @@ impl Command {
             // )),
             CommunicationType::Fault => {
                 // Parse fault data from the command
-                let fault_values = u32::from_le_bytes(self.data[0..4].try_into().unwrap());
-                let warning_values = u32::from_le_bytes(self.data[4..8].try_into().unwrap());
-
-                let fault_feedback = FaultFeedback {
-                    phase_a_overcurrent: (fault_values & (1 << 13)) != 0,
-                    overload_fault: (fault_values & (1 << 14)) != 0,
-                    encoder_not_calibrated: (fault_values & (1 << 7)) != 0,
-                    phase_c_overcurrent: (fault_values & (1 << 12)) != 0,
-                    phase_b_overcurrent: (fault_values & (1 << 11)) != 0,
-                    overvoltage_fault: (fault_values & (1 << 3)) != 0,
-                    undervoltage_fault: (fault_values & (1 << 2)) != 0,
-                    driver_chip_failure: (fault_values & (1 << 1)) != 0,
-                    motor_over_temp_fault: (fault_values & 1) != 0,
-                    motor_over_temp_warning: (warning_values & 1) != 0,
-                };
+                let fault_values = get_value_le(&self.data, 0);
+                let warning_values = get_value_le(&self.data, 4);
+
+                let fault_feedback = FaultFeedback::from_bits(fault_values, warning_values);
 
                 Ok(Frame::Fault(fault_feedback))
             }
@@ -161,7 +239,7 @@ impl Serialize for Command {
         S: serde::Serializer,
     {
         let (id, data) = self.to_can_packet();
-        (id, data).serialize(serializer)
+        (id, &data[..]).serialize(serializer)
     }
 }
 
@@ -171,7 +249,7 @@ impl<'de> Deserialize<'de> for Command {
         D: serde::Deserializer<'de>,
     {
         let (id, data): (u32, Vec<u8>) = Deserialize::deserialize(deserializer)?;
-        Ok(Command::from_can_packet(id, data))
+        Command::from_can_packet(id, &data).map_err(serde::de::Error::custom)
     }
 }
 
@@ -179,7 +257,7 @@ pub trait CommandData {
     fn command_type(&self) -> CommunicationType;
     fn from_command(cmd: Command) -> Self;
     fn to_command(&self, can_id: u8) -> Command;
-    fn to_can_packet(&self, can_id: u8) -> (u32, Vec<u8>) {
+    fn to_can_packet(&self, can_id: u8) -> (u32, [u8; 8]) {
         let cmd = self.to_command(can_id);
         cmd.to_can_packet()
     }
@@ -208,11 +286,10 @@ impl CommandData for ControlCommand {
         CommunicationType::Control
     }
     fn from_command(cmd: Command) -> Self {
-        // Convert pairs of bytes to u16 using from_le_bytes
-        let angle_raw = u16::from_be_bytes(cmd.data[0..2].try_into().unwrap());
-        let velocity_raw = u16::from_be_bytes(cmd.data[2..4].try_into().unwrap());
-        let kp_raw = u16::from_be_bytes(cmd.data[4..6].try_into().unwrap());
-        let kd_raw = u16::from_be_bytes(cmd.data[6..8].try_into().unwrap());
+        let angle_raw = get_mit_field_be(&cmd.data, 0);
+        let velocity_raw = get_mit_field_be(&cmd.data, 2);
+        let kp_raw = get_mit_field_be(&cmd.data, 4);
+        let kd_raw = get_mit_field_be(&cmd.data, 6);
 
         // Convert to final values
         let target_angle = denormalize_value(angle_raw as f32, 0.0, 65535.0, -100.0, 100.0);
@@ -243,20 +320,14 @@ impl CommandData for ControlCommand {
         let kd_normalized = normalize_value(self.kd, 0.0, 100.0, 0.0, 65535.0) as u16;
         let torque_normalized = normalize_value(self.torque, -100.0, 100.0, 0.0, 65535.0) as u16;
 
-        // Convert to bytes using to_be_bytes and copy to data array
-        data[0..2].copy_from_slice(&angle_normalized.to_be_bytes());
-        data[2..4].copy_from_slice(&velocity_normalized.to_be_bytes());
-        data[4..6].copy_from_slice(&kp_normalized.to_be_bytes());
-        data[6..8].copy_from_slice(&kd_normalized.to_be_bytes());
+        put_mit_field_be(&mut data, 0, angle_normalized);
+        put_mit_field_be(&mut data, 2, velocity_normalized);
+        put_mit_field_be(&mut data, 4, kp_normalized);
+        put_mit_field_be(&mut data, 6, kd_normalized);
 
         // debug!("angle: {}, data: {:x?}", self.target_angle, &data[0..2]);
 
-        Command::new(
-            data,
-            can_id,
-            torque_normalized as u16,
-            CommunicationType::Control,
-        )
+        Command::new(data, can_id, torque_normalized, CommunicationType::Control)
     }
 }
 
@@ -266,10 +337,10 @@ impl CommandData for FeedbackFrame {
     }
 
     fn from_command(cmd: Command) -> Self {
-        let angle_raw = u16::from_be_bytes(cmd.data[0..2].try_into().unwrap());
-        let velocity_raw = u16::from_be_bytes(cmd.data[2..4].try_into().unwrap());
-        let torque_raw = u16::from_be_bytes(cmd.data[4..6].try_into().unwrap());
-        let temp_raw = u16::from_be_bytes(cmd.data[6..8].try_into().unwrap());
+        let angle_raw = get_mit_field_be(&cmd.data, 0);
+        let velocity_raw = get_mit_field_be(&cmd.data, 2);
+        let torque_raw = get_mit_field_be(&cmd.data, 4);
+        let temp_raw = get_mit_field_be(&cmd.data, 6);
 
         let angle = normalize_value(angle_raw as f32, 0.0, 65535.0, -100.0, 100.0);
         let velocity = normalize_value(velocity_raw as f32, 0.0, 65535.0, -100.0, 100.0);
@@ -314,11 +385,10 @@ impl CommandData for FeedbackFrame {
         let torque_normalized = normalize_value(self.torque, -100.0, 100.0, 0.0, 65535.0) as u16;
         let temperature_normalized = (self.temperature * 10.0) as u16;
 
-        // Convert to bytes using to_be_bytes and copy to data array
-        data[0..2].copy_from_slice(&angle_normalized.to_be_bytes());
-        data[2..4].copy_from_slice(&velocity_normalized.to_be_bytes());
-        data[4..6].copy_from_slice(&torque_normalized.to_be_bytes());
-        data[6..8].copy_from_slice(&temperature_normalized.to_be_bytes());
+        put_mit_field_be(&mut data, 0, angle_normalized);
+        put_mit_field_be(&mut data, 2, velocity_normalized);
+        put_mit_field_be(&mut data, 4, torque_normalized);
+        put_mit_field_be(&mut data, 6, temperature_normalized);
 
         let data_2: u16 = (self.motor_id as u16)
             | ((self.fault_uncalibrated as u16) << 5)
@@ -398,22 +468,21 @@ impl CommandData for WriteCommand {
     fn from_command(cmd: Command) -> Self {
         WriteCommand {
             host_id: cmd.data_2 as u8,
-            parameter_index: u16::from_le_bytes(cmd.data[0..=1].try_into().unwrap()),
-            data: f32::from_le_bytes(cmd.data[4..=7].try_into().unwrap()),
+            parameter_index: get_index(&cmd.data),
+            data: f32::from_bits(get_value_le(&cmd.data, 4)),
         }
     }
     fn to_command(&self, can_id: u8) -> Command {
         let mut data = [0u8; 8];
-        data[0..=1].copy_from_slice(&self.parameter_index.to_le_bytes());
+        put_index(&mut data, self.parameter_index);
 
         if RobStride04Parameter::from_index(self.parameter_index)
             .unwrap_or(RobStride04Parameter::Unknown)
             == RobStride04Parameter::RunMode
         {
-            data[4] = self.data.to_le_bytes()[0];
+            data[4] = self.data as u8;
         } else {
-            let le_data = self.data.to_le_bytes();
-            data[4..=7].copy_from_slice(&le_data);
+            put_value_le(&mut data, 4, self.data.to_bits());
         }
         Command::new(data, can_id, self.host_id as u16, CommunicationType::Write)
     }
@@ -440,15 +509,15 @@ impl CommandData for ReadCommand {
     fn from_command(cmd: Command) -> Self {
         ReadCommand {
             host_id: cmd.data_2 as u8,
-            parameter_index: u16::from_le_bytes(cmd.data[0..=1].try_into().unwrap()),
-            data: u32::from_le_bytes(cmd.data[4..=7].try_into().unwrap()),
+            parameter_index: get_index(&cmd.data),
+            data: get_value_le(&cmd.data, 4),
             read_status: cmd.data[0] == 0,
         }
     }
     fn to_command(&self, can_id: u8) -> Command {
         let mut data = [0u8; 8];
-        data[0..=1].copy_from_slice(&self.parameter_index.to_le_bytes());
-        data[4..=7].copy_from_slice(&self.data.to_le_bytes());
+        put_index(&mut data, self.parameter_index);
+        put_value_le(&mut data, 4, self.data);
         Command::new(data, can_id, self.host_id as u16, CommunicationType::Read)
     }
 }
@@ -464,8 +533,7 @@ impl ReadCommand {
             return data as f32;
         }
         let le_data = u32::from_le(self.data);
-        let float_value = f32::from_bits(le_data);
-        float_value
+        f32::from_bits(le_data)
     }
 }
 
@@ -515,21 +583,10 @@ impl CommandData for FaultFeedback {
     }
 
     fn from_command(cmd: Command) -> Self {
-        let fault_values = u32::from_le_bytes(cmd.data[0..4].try_into().unwrap());
-        let warning_values = u32::from_le_bytes(cmd.data[4..8].try_into().unwrap());
-
-        FaultFeedback {
-            phase_a_overcurrent: (fault_values & (1 << 13)) != 0,
-            overload_fault: (fault_values & (1 << 14)) != 0,
-            encoder_not_calibrated: (fault_values & (1 << 7)) != 0,
-            phase_c_overcurrent: (fault_values & (1 << 12)) != 0,
-            phase_b_overcurrent: (fault_values & (1 << 11)) != 0,
-            overvoltage_fault: (fault_values & (1 << 3)) != 0,
-            undervoltage_fault: (fault_values & (1 << 2)) != 0,
-            driver_chip_failure: (fault_values & (1 << 1)) != 0,
-            motor_over_temp_fault: (fault_values & 1) != 0,
-            motor_over_temp_warning: (warning_values & 1) != 0,
-        }
+        let fault_values = get_value_le(&cmd.data, 0);
+        let warning_values = get_value_le(&cmd.data, 4);
+
+        FaultFeedback::from_bits(fault_values, warning_values)
     }
 
     fn to_command(&self, can_id: u8) -> Command {
@@ -568,9 +625,141 @@ impl CommandData for FaultFeedback {
         }
 
         let mut data = [0u8; 8];
-        data[0..4].copy_from_slice(&fault_values.to_le_bytes());
-        data[4..8].copy_from_slice(&warning_values.to_le_bytes());
+        put_value_le(&mut data, 0, fault_values);
+        put_value_le(&mut data, 4, warning_values);
 
         Command::new(data, can_id, 0, CommunicationType::Fault)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robstride01::{self, RobStride01Command};
+    use crate::robstride04::{self, RobStride04Command};
+
+    // (position rad, velocity rad/s, kp, kd, torque Nm)
+    type Targets = (f32, f32, f32, f32, f32);
+
+    fn robstride01_frame((angle, velocity, kp, kd, torque): Targets) -> (u32, [u8; 8]) {
+        RobStride01Command {
+            target_angle_rad: angle,
+            target_velocity_rads: velocity,
+            kp,
+            kd,
+            torque_nm: torque,
+        }
+        .to_control_command()
+        .to_can_packet(0x01)
+    }
+
+    fn robstride04_frame((angle, velocity, kp, kd, torque): Targets) -> (u32, [u8; 8]) {
+        RobStride04Command {
+            target_angle_rad: angle,
+            target_velocity_rads: velocity,
+            kp,
+            kd,
+            torque_nm: torque,
+        }
+        .to_control_command()
+        .to_can_packet(0x01)
+    }
+
+    // MIT control frames: torque in bits 8-23 of the ID, position, velocity,
+    // kp and kd as big-endian u16s in the data. The expected bytes were
+    // worked out by hand with the manual's conversion,
+    // (x - min) * 65535 / (max - min) truncated, not taken from the encoder.
+    #[test]
+    fn mit_frames_match_the_manual_conversion() {
+        assert_eq!(
+            robstride01_frame((1.0, -2.0, 30.0, 0.7, 3.3)),
+            (
+                0x0198_d801,
+                [0x8a, 0x2f, 0x7a, 0x2e, 0x0f, 0x5c, 0x23, 0xd6]
+            )
+        );
+        assert_eq!(
+            robstride01_frame((-0.5, 0.6, 55.0, 1.3, -4.7)),
+            (
+                0x015c_9c01,
+                [0x7a, 0xe7, 0x81, 0xbe, 0x1c, 0x28, 0x42, 0x8f]
+            )
+        );
+        assert_eq!(
+            robstride04_frame((1.0, -2.0, 310.0, 7.3, 33.3)),
+            (
+                0x01a3_8401,
+                [0x8a, 0x2f, 0x6e, 0xee, 0x0f, 0xdf, 0x12, 0xb0]
+            )
+        );
+        assert_eq!(
+            robstride04_frame((-0.5, 0.7, 55.0, 2.3, -47.0)),
+            (
+                0x014d_dd01,
+                [0x7a, 0xe7, 0x85, 0xf8, 0x02, 0xd0, 0x05, 0xe3]
+            )
+        );
+    }
+
+    // Mid-range values land on 0x7fff, the top of each range on 0xffff and
+    // the bottom on 0x0000
+    #[test]
+    fn mit_range_ends_encode_to_the_u16_ends() {
+        assert_eq!(
+            robstride04_frame((0.0, 0.0, 0.0, 0.0, 0.0)),
+            (
+                0x017f_ff01,
+                [0x7f, 0xff, 0x7f, 0xff, 0x00, 0x00, 0x00, 0x00]
+            )
+        );
+
+        let limits = robstride01::LIMITS;
+        assert_eq!(
+            robstride01_frame((
+                limits.max_angle,
+                limits.max_velocity,
+                limits.max_kp,
+                limits.max_kd,
+                limits.max_torque,
+            )),
+            (0x01ff_ff01, [0xff; 8])
+        );
+
+        let limits = robstride04::LIMITS;
+        assert_eq!(
+            robstride04_frame((
+                limits.min_angle,
+                limits.min_velocity,
+                limits.min_kp,
+                limits.min_kd,
+                limits.min_torque,
+            )),
+            (0x0100_0001, [0x00; 8])
+        );
+    }
+
+    // Out of range targets saturate at the ends of the range instead of
+    // wrapping
+    #[test]
+    fn out_of_range_mit_targets_saturate() {
+        assert_eq!(
+            robstride04_frame((100.0, -100.0, 1e6, 1e6, -1e6)),
+            (
+                0x0100_0001,
+                [0xff, 0xff, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff]
+            )
+        );
+    }
+
+    // Feedback mode bits 22-23: 2 is run mode, 3 is undefined and rejected
+    #[test]
+    fn feedback_with_an_undefined_mode_is_rejected() {
+        let feedback = Command::from_can_packet(0x0280_0101, &[0x7f; 8]).unwrap();
+        assert!(feedback.to_frame().is_ok());
+        let feedback = Command::from_can_packet(0x02c0_0101, &[0x7f; 8]).unwrap();
+        assert_eq!(
+            feedback.to_frame().unwrap_err(),
+            "Unknown motor mode: 3".to_string()
+        );
+    }
+}
@@ -1,4 +1,6 @@
 use num_derive::{FromPrimitive, ToPrimitive};
+use std::fmt;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Copy, FromPrimitive)]
 pub enum CommunicationType {
@@ -134,6 +136,18 @@ pub struct FeedbackFrame {
     pub motor_id: u8,
 }
 
+impl FeedbackFrame {
+    /// Returns whether any of the fault bits is set.
+    pub fn has_fault(&self) -> bool {
+        self.fault_uncalibrated
+            || self.fault_hall_encoding
+            || self.fault_magnetic_encoding
+            || self.fault_over_temperature
+            || self.fault_overcurrent
+            || self.fault_undervoltage
+    }
+}
+
 #[derive(Debug, Clone, Copy, FromPrimitive, ToPrimitive, PartialEq)]
 pub enum MotorMode {
     Reset = 0,       // Reset mode
@@ -141,6 +155,15 @@ pub enum MotorMode {
     Run = 2,         // Motor mode
 }
 
+// Value of the run mode parameter (0x7005)
+#[derive(Debug, Clone, Copy, FromPrimitive, ToPrimitive, PartialEq)]
+pub enum RunMode {
+    Operation = 0, // MIT control
+    Position = 1,
+    Speed = 2,
+    Current = 3,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct FaultFrame {
     pub fault_code: u16,
@@ -204,11 +227,64 @@ pub struct FaultFeedback {
     pub motor_over_temp_warning: bool,
 }
 
+impl FaultFeedback {
+    /// Decodes the fault (bytes 0-3) and warning (bytes 4-7) registers of a
+    /// fault feedback frame.
+    pub fn from_bits(faults: u32, warnings: u32) -> Self {
+        FaultFeedback {
+            phase_a_overcurrent: (faults & (1 << 13)) != 0,
+            overload_fault: (faults & (1 << 14)) != 0,
+            encoder_not_calibrated: (faults & (1 << 7)) != 0,
+            phase_c_overcurrent: (faults & (1 << 12)) != 0,
+            phase_b_overcurrent: (faults & (1 << 11)) != 0,
+            overvoltage_fault: (faults & (1 << 3)) != 0,
+            undervoltage_fault: (faults & (1 << 2)) != 0,
+            driver_chip_failure: (faults & (1 << 1)) != 0,
+            motor_over_temp_fault: (faults & 1) != 0,
+            motor_over_temp_warning: (warnings & 1) != 0,
+        }
+    }
+
+    /// Names of the faults and warnings that are set.
+    pub fn active(&self) -> Vec<&'static str> {
+        [
+            (self.phase_a_overcurrent, "phase A overcurrent"),
+            (self.phase_b_overcurrent, "phase B overcurrent"),
+            (self.phase_c_overcurrent, "phase C overcurrent"),
+            (self.overload_fault, "overload"),
+            (self.encoder_not_calibrated, "encoder not calibrated"),
+            (self.overvoltage_fault, "overvoltage"),
+            (self.undervoltage_fault, "undervoltage"),
+            (self.driver_chip_failure, "driver chip failure"),
+            (self.motor_over_temp_fault, "motor over temperature"),
+            (
+                self.motor_over_temp_warning,
+                "motor over temperature warning",
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name))
+        .collect()
+    }
+}
+
+impl fmt::Display for FaultFeedback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let active = self.active();
+        if active.is_empty() {
+            write!(f, "no faults")
+        } else {
+            write!(f, "{}", active.join(", "))
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TxCommand {
-    Send { id: u32, data: Vec<u8> },
+    Send { id: u32, data: [u8; 8] },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ActuatorMeasurementLimits {
     pub min_angle: f32,
     pub max_angle: f32,
@@ -222,12 +298,182 @@ pub struct ActuatorMeasurementLimits {
     pub max_kd: f32,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MeasurementField {
+    Angle,    // rad
+    Velocity, // rad/s
+    Torque,   // Nm
+    Kp,
+    Kd,
+}
+
+impl MeasurementField {
+    pub fn iter() -> impl Iterator<Item = MeasurementField> {
+        use MeasurementField::*;
+        [Angle, Velocity, Torque, Kp, Kd].iter().cloned()
+    }
+}
+
+impl ActuatorMeasurementLimits {
+    /// Returns the (min, max) range of a field.
+    pub fn limit(&self, field: MeasurementField) -> (f32, f32) {
+        match field {
+            MeasurementField::Angle => (self.min_angle, self.max_angle),
+            MeasurementField::Velocity => (self.min_velocity, self.max_velocity),
+            MeasurementField::Torque => (self.min_torque, self.max_torque),
+            MeasurementField::Kp => (self.min_kp, self.max_kp),
+            MeasurementField::Kd => (self.min_kd, self.max_kd),
+        }
+    }
+
+    /// Returns the smallest step of a field on the wire. Control and feedback
+    /// frames carry every field as a 16-bit value spread over the field's
+    /// range, so for the angle this is the MIT position quantization. It says
+    /// nothing about the encoder's own resolution, which the drive doesn't
+    /// report.
+    pub fn resolution(&self, field: MeasurementField) -> f32 {
+        let (min, max) = self.limit(field);
+        (max - min) / u16::MAX as f32
+    }
+}
+
+impl ActuatorType {
+    /// Returns the measurement limits used to scale commands and feedback.
+    pub fn limits(&self) -> ActuatorMeasurementLimits {
+        match self {
+            ActuatorType::RobStride00 => crate::robstride00::LIMITS,
+            ActuatorType::RobStride01 => crate::robstride01::LIMITS,
+            ActuatorType::RobStride02 => crate::robstride02::LIMITS,
+            ActuatorType::RobStride03 => crate::robstride03::LIMITS,
+            ActuatorType::RobStride04 => crate::robstride04::LIMITS,
+        }
+    }
+
+    pub fn torque_rating(&self) -> TorqueRating {
+        match self {
+            ActuatorType::RobStride00 => crate::robstride00::TORQUE_RATING,
+            ActuatorType::RobStride01 => crate::robstride01::TORQUE_RATING,
+            ActuatorType::RobStride02 => crate::robstride02::TORQUE_RATING,
+            ActuatorType::RobStride03 => crate::robstride03::TORQUE_RATING,
+            ActuatorType::RobStride04 => crate::robstride04::TORQUE_RATING,
+        }
+    }
+
+    /// Largest current mode Iq command (`IqRef`) of the model, in amps. The
+    /// command range is symmetric.
+    pub fn max_current(&self) -> f32 {
+        let metadata = match self {
+            ActuatorType::RobStride00 => crate::robstride00::RobStride00Parameter::IqRef.metadata(),
+            ActuatorType::RobStride01 => crate::robstride01::RobStride01Parameter::IqRef.metadata(),
+            ActuatorType::RobStride02 => crate::robstride02::RobStride02Parameter::IqRef.metadata(),
+            ActuatorType::RobStride03 => crate::robstride03::RobStride03Parameter::IqRef.metadata(),
+            ActuatorType::RobStride04 => crate::robstride04::RobStride04Parameter::IqRef.metadata(),
+        };
+        metadata.max_value.unwrap_or(0.0)
+    }
+
+    /// MIT gains `(kp, kd)` that make a joint with rotational `inertia`
+    /// (kg m^2, as seen at the output) respond like a second-order system with
+    /// natural frequency `omega_n` (rad/s) and damping ratio `zeta`:
+    /// kp = inertia * omega_n^2 and kd = 2 * zeta * omega_n * inertia. Both
+    /// are clamped to the model's gain range. A starting point for tuning;
+    /// friction and gravity are not accounted for.
+    pub fn gains_for(&self, inertia: f32, omega_n: f32, zeta: f32) -> (f32, f32) {
+        let limits = self.limits();
+        let kp = (inertia * omega_n * omega_n).clamp(limits.min_kp, limits.max_kp);
+        let kd = (2.0 * zeta * omega_n * inertia).clamp(limits.min_kd, limits.max_kd);
+        (kp, kd)
+    }
+}
+
+/// Continuous (rated) and short-term (peak) torque of a model, in Nm. The
+/// peak torque is the edge of the command range and can't be held
+/// continuously.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TorqueRating {
+    pub rated: f32,
+    pub peak: f32,
+}
+
+/// Temperatures (Celsius) used to derate an actuator as it heats up. The
+/// headroom is 1.0 up to `derate_start` and falls linearly to 0.0 at `max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThermalLimits {
+    pub derate_start: f32,
+    pub max: f32,
+}
+
+impl ThermalLimits {
+    pub fn headroom(&self, temperature: f32) -> f32 {
+        if temperature <= self.derate_start {
+            return 1.0;
+        }
+        ((self.max - temperature) / (self.max - self.derate_start)).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for ThermalLimits {
+    // Conservative defaults, well below the driver over-temperature fault
+    fn default() -> Self {
+        ThermalLimits {
+            derate_start: 70.0,
+            max: 90.0,
+        }
+    }
+}
+
+/// Thresholds for flagging sustained oscillation from feedback velocity. A
+/// reversal counts when the velocity swings past `min_velocity` (rad/s) in the
+/// opposite direction, and the actuator is oscillating when the reversals
+/// within `window` amount to at least `min_frequency_hz` cycles per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OscillationLimits {
+    pub min_velocity: f32,
+    pub min_frequency_hz: f32,
+    pub window: Duration,
+}
+
+impl Default for OscillationLimits {
+    fn default() -> Self {
+        OscillationLimits {
+            min_velocity: 0.5,
+            min_frequency_hz: 5.0,
+            window: Duration::from_secs(1),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ActuatorConfiguration {
     pub actuator_type: ActuatorType,
     pub max_angle_change: Option<f32>,
     pub max_velocity: Option<f32>,
     pub command_rate_hz: Option<f32>,
+    pub thermal_limits: ThermalLimits,
+    // Scale kp, kd and torque by the thermal headroom when commanding
+    pub thermal_derating: bool,
+    pub oscillation_limits: OscillationLimits,
+    // Halve kp and kd each time oscillation is detected
+    pub reduce_gains_on_oscillation: bool,
+    // When no new target arrives, advance the target position along the
+    // target velocity for up to this long instead of holding it, so a late
+    // update doesn't stall a moving joint. None holds the last target.
+    pub target_extrapolation: Option<Duration>,
+    // When a moving target (nonzero target velocity) hasn't been updated for
+    // this long, treat the trajectory feeding it as stalled instead of
+    // commanding the stale point. None disables the check.
+    pub max_target_age: Option<Duration>,
+    // On a stalled trajectory, hold the position from the last feedback
+    // instead of skipping commands until a new target arrives
+    pub freeze_stale_targets: bool,
+    // Enable the actuator when it is commanded while in reset mode, instead
+    // of returning an error
+    pub auto_start: bool,
+    // Multiplies the torque feedforward sent to the drive, 1 or -1. RobStride
+    // drives apply positive torque toward increasing angle, the direction of
+    // positive position and velocity targets; -1 flips the feedforward for
+    // joints where it acts against the position convention.
+    pub torque_sign: f32,
 }
 
 impl Default for ActuatorConfiguration {
@@ -237,6 +483,82 @@ impl Default for ActuatorConfiguration {
             max_angle_change: None,
             max_velocity: None,
             command_rate_hz: None,
+            thermal_limits: ThermalLimits::default(),
+            thermal_derating: false,
+            oscillation_limits: OscillationLimits::default(),
+            reduce_gains_on_oscillation: false,
+            target_extrapolation: None,
+            max_target_age: None,
+            freeze_stale_targets: false,
+            auto_start: false,
+            torque_sign: 1.0,
+        }
+    }
+}
+
+impl ActuatorConfiguration {
+    /// Rejects a `command_rate_hz` that isn't positive and finite, since the
+    /// run loop derives its minimum command interval from it, a `torque_sign`
+    /// other than 1 or -1, and `oscillation_limits` with a zero window or a
+    /// velocity or frequency threshold that isn't positive and finite, which
+    /// would flag the actuator as oscillating on every frame.
+    pub fn validate(&self) -> eyre::Result<()> {
+        if let Some(rate_hz) = self.command_rate_hz {
+            if !(rate_hz.is_finite() && rate_hz > 0.0) {
+                return Err(eyre::eyre!(
+                    "command_rate_hz must be positive and finite, got {}",
+                    rate_hz
+                ));
+            }
+        }
+        if self.torque_sign != 1.0 && self.torque_sign != -1.0 {
+            return Err(eyre::eyre!(
+                "torque_sign must be 1 or -1, got {}",
+                self.torque_sign
+            ));
         }
+        let limits = &self.oscillation_limits;
+        if !(limits.min_velocity.is_finite() && limits.min_velocity > 0.0) {
+            return Err(eyre::eyre!(
+                "oscillation_limits.min_velocity must be positive and finite, got {}",
+                limits.min_velocity
+            ));
+        }
+        if !(limits.min_frequency_hz.is_finite() && limits.min_frequency_hz > 0.0) {
+            return Err(eyre::eyre!(
+                "oscillation_limits.min_frequency_hz must be positive and finite, got {}",
+                limits.min_frequency_hz
+            ));
+        }
+        if limits.window.is_zero() {
+            return Err(eyre::eyre!("oscillation_limits.window must be non-zero"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CommandData;
+
+    // Fault register bits: 0 over temperature, 2 undervoltage, 13 phase A
+    #[test]
+    fn fault_bits_decode_to_named_faults() {
+        let faults = FaultFeedback::from_bits(0, 0);
+        assert!(faults.active().is_empty());
+        assert_eq!(faults.to_string(), "no faults");
+
+        let faults = FaultFeedback::from_bits((1 << 13) | (1 << 2), 0);
+        assert!(faults.phase_a_overcurrent && faults.undervoltage_fault);
+        assert_eq!(faults.to_string(), "phase A overcurrent, undervoltage");
+
+        let faults = FaultFeedback::from_bits(1, 1);
+        assert_eq!(
+            faults.active(),
+            ["motor over temperature", "motor over temperature warning"]
+        );
+        let (_, data) = faults.to_can_packet(0x01);
+        assert_eq!(data, [0x01, 0, 0, 0, 0x01, 0, 0, 0]);
     }
 }
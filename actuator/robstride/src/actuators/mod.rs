@@ -3,3 +3,101 @@ pub mod robstride01;
 pub mod robstride02;
 pub mod robstride03;
 pub mod robstride04;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actuator::TypedCommandData;
+    use crate::ActuatorMeasurementLimits;
+
+    fn assert_round_trip<C: TypedCommandData>(
+        model: &str,
+        limits: &ActuatorMeasurementLimits,
+        make: impl Fn(f32, f32, f32, f32, f32) -> C,
+    ) {
+        let cases = [
+            (0.0, 0.0, 0.0, 0.0, 0.0),
+            (1.0, -2.0, 30.0, 0.5, 3.0),
+            (
+                limits.min_angle,
+                limits.max_velocity,
+                limits.max_kp,
+                limits.max_kd,
+                limits.min_torque,
+            ),
+            (
+                limits.max_angle,
+                limits.min_velocity,
+                limits.min_kp,
+                limits.min_kd,
+                limits.max_torque,
+            ),
+        ];
+        for (angle, velocity, kp, kd, torque) in cases {
+            let command = make(angle, velocity, kp, kd, torque);
+            let decoded = C::from_control_command(command.to_control_command());
+            let pairs = [
+                ("angle", angle, decoded.target_angle_rad()),
+                ("velocity", velocity, decoded.target_velocity_rads()),
+                ("kp", kp, decoded.kp()),
+                ("kd", kd, decoded.kd()),
+                ("torque", torque, decoded.torque_nm()),
+            ];
+            for (field, sent, back) in pairs {
+                assert!(
+                    (sent - back).abs() < 1e-3,
+                    "{model} {field}: sent {sent}, decoded {back}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn typed_commands_survive_the_control_command_round_trip() {
+        assert_round_trip("RobStride00", &robstride00::LIMITS, |a, v, kp, kd, t| {
+            robstride00::RobStride00Command {
+                target_angle_rad: a,
+                target_velocity_rads: v,
+                kp,
+                kd,
+                torque_nm: t,
+            }
+        });
+        assert_round_trip("RobStride01", &robstride01::LIMITS, |a, v, kp, kd, t| {
+            robstride01::RobStride01Command {
+                target_angle_rad: a,
+                target_velocity_rads: v,
+                kp,
+                kd,
+                torque_nm: t,
+            }
+        });
+        assert_round_trip("RobStride02", &robstride02::LIMITS, |a, v, kp, kd, t| {
+            robstride02::RobStride02Command {
+                target_angle_rad: a,
+                target_velocity_rads: v,
+                kp,
+                kd,
+                torque_nm: t,
+            }
+        });
+        assert_round_trip("RobStride03", &robstride03::LIMITS, |a, v, kp, kd, t| {
+            robstride03::RobStride03Command {
+                target_angle_rad: a,
+                target_velocity_rads: v,
+                kp,
+                kd,
+                torque_nm: t,
+            }
+        });
+        assert_round_trip("RobStride04", &robstride04::LIMITS, |a, v, kp, kd, t| {
+            robstride04::RobStride04Command {
+                target_angle_rad: a,
+                target_velocity_rads: v,
+                kp,
+                kd,
+                torque_nm: t,
+            }
+        });
+    }
+}
@@ -4,15 +4,15 @@ use crate::actuator_types::{
 use crate::{
     actuator::{denormalize_value, normalize_value, TypedCommandData, TypedFeedbackData},
     Actuator, ActuatorMeasurementLimits, ActuatorType, Command, CommandData, CommunicationType,
-    ControlCommand, FeedbackFrame, ObtainIDCommand, ParaStrInfo, ReadCommand, SetIDCommand,
-    SetZeroCommand, StopCommand, WriteCommand,
+    ControlCommand, FeedbackFrame, ObtainIDCommand, ParaStrInfo, ReadCommand, RunMode,
+    SetIDCommand, SetZeroCommand, StopCommand, TorqueRating, WriteCommand,
 };
 use async_trait::async_trait;
 use eyre::{Result, WrapErr};
 use std::f32::consts::PI;
 use tokio::sync::mpsc;
 
-const LIMITS: ActuatorMeasurementLimits = ActuatorMeasurementLimits {
+pub const LIMITS: ActuatorMeasurementLimits = ActuatorMeasurementLimits {
     min_angle: -4.0 * PI,
     max_angle: 4.0 * PI,
     min_velocity: -44.0,
@@ -25,6 +25,11 @@ const LIMITS: ActuatorMeasurementLimits = ActuatorMeasurementLimits {
     max_kd: 5.0,
 };
 
+pub const TORQUE_RATING: TorqueRating = TorqueRating {
+    rated: 6.0,
+    peak: 17.0,
+};
+
 #[derive(Debug, Clone, Default)]
 pub struct RobStride01Command {
     pub target_angle_rad: f32,     // Radians
@@ -97,6 +102,26 @@ impl TypedCommandData for RobStride01Command {
             ),
         }
     }
+
+    fn target_angle_rad(&self) -> f32 {
+        self.target_angle_rad
+    }
+
+    fn target_velocity_rads(&self) -> f32 {
+        self.target_velocity_rads
+    }
+
+    fn kp(&self) -> f32 {
+        self.kp
+    }
+
+    fn kd(&self) -> f32 {
+        self.kd
+    }
+
+    fn torque_nm(&self) -> f32 {
+        self.torque_nm
+    }
 }
 
 impl TypedFeedbackData for RobStride01Feedback {
@@ -162,6 +187,10 @@ impl Actuator for RobStride01 {
         ActuatorType::RobStride01
     }
 
+    fn host_id(&self) -> u8 {
+        self.host_id
+    }
+
     async fn enable(&self) -> Result<()> {
         let cmd = EnableCommand {
             host_id: self.host_id,
@@ -345,6 +374,16 @@ impl Actuator for RobStride01 {
         };
         self.write_parameter(cmd).await
     }
+
+    async fn set_run_mode(&self, mode: RunMode) -> Result<()> {
+        let param = RobStride01Parameter::RunMode;
+        let cmd = WriteCommand {
+            host_id: self.host_id,
+            parameter_index: param.metadata().index,
+            data: mode as u8 as f32,
+        };
+        self.write_parameter(cmd).await
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -0,0 +1,151 @@
+use robstride::CH341Decoder;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+// Counts heap allocations made by the parsing loops below
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const FRAMES: usize = 100_000;
+// Bytes handed over by each simulated serial read: one 17-byte frame, so
+// both paths see every frame whole and decode the same number
+const READ_SIZE: usize = 17;
+
+fn feedback_frame() -> Vec<u8> {
+    let id: u32 = 0x0200_0100;
+    let mut pkt = Vec::new();
+    pkt.extend_from_slice(b"AT");
+    pkt.extend_from_slice(&((id << 3) | 0x4).to_be_bytes());
+    pkt.push(8);
+    pkt.extend_from_slice(&[0x7f, 0xfe, 0x80, 0x73, 0x7f, 0xff, 0x01, 0x18]);
+    pkt.extend_from_slice(b"\r\n");
+    pkt
+}
+
+// The previous `parse_message`, returning `None` where it returned an error
+fn parse_message(buf: &[u8]) -> Option<Vec<u8>> {
+    if buf.len() < 8 || buf[0] != b'A' || buf[1] != b'T' {
+        return None;
+    }
+    let data_len = buf[6] as usize;
+    let total_len = 7 + data_len + 2;
+    if buf.len() < total_len || buf[total_len - 2] != b'\r' || buf[total_len - 1] != b'\n' {
+        return None;
+    }
+    Some(buf[7..7 + data_len].to_vec())
+}
+
+// The previous receive path, one call per frame: a fresh 1024-byte buffer,
+// reads appended to it until it holds a whole frame, and a `Vec` for that
+// frame's data. Bytes read past the frame were dropped with the buffer.
+fn legacy(stream: &[u8]) -> usize {
+    let mut decoded = 0;
+    let mut offset = 0;
+    'recv: while offset < stream.len() {
+        let mut buf = vec![0; 1024];
+        let mut pos = 0;
+        while offset < stream.len() {
+            let n = READ_SIZE.min(stream.len() - offset);
+            buf[pos..pos + n].copy_from_slice(&stream[offset..offset + n]);
+            offset += n;
+            pos += n;
+
+            for i in 0..pos.saturating_sub(7) {
+                if buf[i] == b'A' && buf[i + 1] == b'T' {
+                    if let Some(data) = parse_message(&buf[i..pos]) {
+                        black_box(data);
+                        decoded += 1;
+                        continue 'recv;
+                    }
+                }
+            }
+        }
+    }
+    decoded
+}
+
+fn decoder(stream: &[u8]) -> usize {
+    let mut decoder = CH341Decoder::new();
+    let mut decoded = 0;
+    let mut offset = 0;
+    while offset < stream.len() {
+        let spare = decoder.spare().expect("buffer full");
+        let n = READ_SIZE.min(stream.len() - offset).min(spare.len());
+        spare[..n].copy_from_slice(&stream[offset..offset + n]);
+        offset += n;
+        decoder.commit(n);
+
+        while let Some((_id, data)) = decoder.next_frame() {
+            black_box(data);
+            decoded += 1;
+        }
+    }
+    decoded
+}
+
+struct Run {
+    decoded: usize,
+    allocations: usize,
+    elapsed: Duration,
+}
+
+fn run(stream: &[u8], f: fn(&[u8]) -> usize) -> Run {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let decoded = f(stream);
+    let elapsed = start.elapsed();
+    Run {
+        decoded,
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - before,
+        elapsed,
+    }
+}
+
+fn main() {
+    let frame = feedback_frame();
+    let stream: Vec<u8> = frame
+        .iter()
+        .copied()
+        .cycle()
+        .take(frame.len() * FRAMES)
+        .collect();
+
+    let runs = [
+        ("legacy", run(&stream, legacy)),
+        ("decoder", run(&stream, decoder)),
+    ];
+    for (name, run) in &runs {
+        assert_eq!(
+            run.decoded, FRAMES,
+            "{} decoded {} of {} frames",
+            name, run.decoded, FRAMES
+        );
+    }
+    for (name, run) in &runs {
+        println!(
+            "{:<8} frames={:<7} allocations={:<7} ({:.3}/frame) time={:?}",
+            name,
+            run.decoded,
+            run.allocations,
+            run.allocations as f64 / run.decoded as f64,
+            run.elapsed
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use eyre::{Result, WrapErr};
+use num_traits::FromPrimitive;
+use robstride::{
+    robstride00::RobStride00, robstride01::RobStride01, robstride02::RobStride02,
+    robstride03::RobStride03, robstride04::RobStride04, Actuator, ActuatorConfiguration,
+    ActuatorType, CH341Transport, ControlConfig, SocketCanTransport, StubTransport, Supervisor,
+    TransportType,
+};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tracing::error;
+use tracing_subscriber::EnvFilter;
+
+const TRANSPORT: &str = "cli";
+const HOST_ID: u8 = 0xFD;
+
+const USAGE: &str = "usage: cli <stub|/dev/ttyUSBx|can0> [<id>:<type> ...]
+  e.g. cli can0 1:4 2:2 (types 0-4 select RobStride00-04)";
+
+const HELP: &str = "commands:
+  scan                      scan the bus and list responding IDs
+  ids                       list configured actuators
+  enable <id>               enable an actuator
+  disable <id> [clear]      disable an actuator, optionally clearing faults
+  set <id> [pos <rad>] [vel <rad/s>] [torque <Nm>] [kp <kp> kd <kd>]
+                            set the target, and the gains if given
+  zero <id>                 set the current position as zero
+  feedback <id>             print the latest feedback
+  faults <id>               print the fault flags of the latest feedback
+  help                      show this message
+  quit                      disable all actuators and exit";
+
+fn parse_layout(arg: &str) -> Result<(u8, ActuatorType)> {
+    let (id, actuator_type) = arg
+        .split_once(':')
+        .ok_or_else(|| eyre::eyre!("expected <id>:<type>, got {:?}", arg))?;
+    let id = id
+        .parse()
+        .wrap_err_with(|| format!("invalid ID {:?}", id))?;
+    let actuator_type = actuator_type
+        .parse()
+        .ok()
+        .and_then(ActuatorType::from_u8)
+        .ok_or_else(|| eyre::eyre!("invalid actuator type {:?}", actuator_type))?;
+    Ok((id, actuator_type))
+}
+
+async fn open_transport(port: &str) -> Result<TransportType> {
+    let transport = if port == "stub" {
+        TransportType::Stub(StubTransport::new(port.to_string()))
+    } else if port.starts_with("/dev/") {
+        TransportType::CH341(CH341Transport::new(port.to_string()).await?)
+    } else {
+        TransportType::SocketCAN(SocketCanTransport::new(port.to_string()).await?)
+    };
+    Ok(transport)
+}
+
+async fn add_actuators(supervisor: &Supervisor, layout: &[(u8, ActuatorType)]) -> Result<()> {
+    let tx = supervisor.get_transport_tx(TRANSPORT).await?;
+    for &(id, actuator_type) in layout {
+        let actuator: Box<dyn Actuator> = match actuator_type {
+            ActuatorType::RobStride00 => Box::new(RobStride00::new(id, HOST_ID, tx.clone())),
+            ActuatorType::RobStride01 => Box::new(RobStride01::new(id, HOST_ID, tx.clone())),
+            ActuatorType::RobStride02 => Box::new(RobStride02::new(id, HOST_ID, tx.clone())),
+            ActuatorType::RobStride03 => Box::new(RobStride03::new(id, HOST_ID, tx.clone())),
+            ActuatorType::RobStride04 => Box::new(RobStride04::new(id, HOST_ID, tx.clone())),
+        };
+        supervisor
+            .add_actuator(
+                actuator,
+                ActuatorConfiguration {
+                    actuator_type,
+                    ..Default::default()
+                },
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+fn parse_id(arg: Option<&str>) -> Result<u8> {
+    let arg = arg.ok_or_else(|| eyre::eyre!("missing actuator ID"))?;
+    arg.parse()
+        .wrap_err_with(|| format!("invalid actuator ID {:?}", arg))
+}
+
+async fn set(supervisor: &mut Supervisor, args: &[&str]) -> Result<()> {
+    let id = parse_id(args.first().copied())?;
+    let (mut position, mut velocity, mut torque) = (0.0, 0.0, 0.0);
+    let (mut kp, mut kd) = (None, None);
+
+    for pair in args[1..].chunks(2) {
+        let [key, value] = pair else {
+            return Err(eyre::eyre!("missing value for {:?}", pair[0]));
+        };
+        let value: f32 = value
+            .parse()
+            .wrap_err_with(|| format!("invalid value for {}: {:?}", key, value))?;
+        match *key {
+            "pos" => position = value,
+            "vel" => velocity = value,
+            "torque" => torque = value,
+            "kp" => kp = Some(value),
+            "kd" => kd = Some(value),
+            _ => return Err(eyre::eyre!("unknown field {:?}", key)),
+        }
+    }
+
+    match (kp, kd) {
+        (Some(kp), Some(kd)) => {
+            supervisor
+                .configure(
+                    id,
+                    ControlConfig {
+                        kp,
+                        kd,
+                        max_torque: None,
+                        max_velocity: None,
+                        max_current: None,
+                    },
+                )
+                .await?
+        }
+        (None, None) => {}
+        _ => return Err(eyre::eyre!("kp and kd must be given together")),
+    }
+
+    let applied = supervisor.command(id, position, velocity, torque).await?;
+    if applied != (position, velocity, torque) {
+        println!(
+            "clamped to position={:.3} rad, velocity={:.3} rad/s, torque={:.3} Nm",
+            applied.0, applied.1, applied.2
+        );
+    }
+    Ok(())
+}
+
+async fn execute(
+    supervisor: &mut Supervisor,
+    layout: &[(u8, ActuatorType)],
+    line: &str,
+) -> Result<bool> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let Some((&command, args)) = words.split_first() else {
+        return Ok(true);
+    };
+
+    match command {
+        "scan" => {
+            let configs: Vec<(u8, ActuatorConfiguration)> = layout
+                .iter()
+                .map(|&(id, actuator_type)| {
+                    (
+                        id,
+                        ActuatorConfiguration {
+                            actuator_type,
+                            ..Default::default()
+                        },
+                    )
+                })
+                .collect();
+            let ids = supervisor.scan_bus(HOST_ID, TRANSPORT, &configs).await?;
+            println!("discovered: {:?}", ids);
+        }
+        "ids" => println!("{:?}", supervisor.actuator_ids().await),
+        "enable" => supervisor.enable(parse_id(args.first().copied())?).await?,
+        "disable" => {
+            let id = parse_id(args.first().copied())?;
+            supervisor
+                .disable(id, args.get(1) == Some(&"clear"))
+                .await?
+        }
+        "set" => set(supervisor, args).await?,
+        "zero" => supervisor.zero(parse_id(args.first().copied())?).await?,
+        "feedback" => {
+            let id = parse_id(args.first().copied())?;
+            match supervisor.get_feedback(id).await? {
+                Some((feedback, _)) => println!(
+                    "angle={:.3} rad, velocity={:.3} rad/s, torque={:.3} Nm, temperature={:.1} C, mode={:?}",
+                    feedback.angle,
+                    feedback.velocity,
+                    feedback.torque,
+                    feedback.temperature,
+                    feedback.mode
+                ),
+                None => println!("no feedback yet"),
+            }
+        }
+        "faults" => {
+            let id = parse_id(args.first().copied())?;
+            match supervisor.get_feedback(id).await? {
+                Some((feedback, _)) => println!(
+                    "uncalibrated={} hall_encoding={} magnetic_encoding={} over_temperature={} overcurrent={} undervoltage={}",
+                    feedback.fault_uncalibrated,
+                    feedback.fault_hall_encoding,
+                    feedback.fault_magnetic_encoding,
+                    feedback.fault_over_temperature,
+                    feedback.fault_overcurrent,
+                    feedback.fault_undervoltage
+                ),
+                None => println!("no feedback yet"),
+            }
+        }
+        "help" => println!("{}", HELP),
+        "quit" | "exit" => return Ok(false),
+        _ => println!("unknown command {:?}, try help", command),
+    }
+    Ok(true)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .init();
+
+    let mut args = std::env::args().skip(1);
+    let Some(port) = args.next() else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+    let layout = args
+        .map(|arg| parse_layout(&arg))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut supervisor = Supervisor::new(Duration::from_millis(1000))?;
+    supervisor
+        .add_transport(TRANSPORT.to_string(), open_transport(&port).await?)
+        .await?;
+    add_actuators(&supervisor, &layout).await?;
+
+    let mut supervisor_runner = supervisor.clone_controller();
+    supervisor_runner.set_stats_interval(None);
+    let supervisor_handle = tokio::spawn(async move {
+        if let Err(e) = supervisor_runner.run(Duration::from_millis(10)).await {
+            error!("Supervisor task failed: {}", e);
+        }
+    });
+
+    println!("{}", HELP);
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
+        match execute(&mut supervisor, &layout, &line).await {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => println!("error: {}", e),
+        }
+    }
+
+    supervisor.shutdown().await?;
+    supervisor_handle.abort();
+    Ok(())
+}
@@ -1,8 +1,7 @@
 use eyre::Result;
 use robstride::robstride03::{RobStride03, RobStride03Command};
 use robstride::SocketCanTransport;
-use robstride::{Actuator, ControlCommand, Protocol, Transport, TxCommand, TypedCommandData};
-use std::sync::Arc;
+use robstride::{Actuator, Transport, TxCommand, TypedCommandData};
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tracing::{error, info, trace};
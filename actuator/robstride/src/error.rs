@@ -0,0 +1,58 @@
+use std::fmt;
+
+use crate::MotorMode;
+
+/// Failures that callers may need to tell apart. Functions return
+/// `eyre::Result`, so match on the kind with
+/// `report.downcast_ref::<RobstrideError>()`. I/O and serial port failures
+/// keep their own error types and downcast to `std::io::Error` and
+/// `tokio_serial::Error` the same way.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RobstrideError {
+    ActuatorNotFound(u8),
+    TransportNotFound(String),
+    // The actuator is in a mode where it ignores the request
+    UnexpectedMode {
+        id: u8,
+        expected: MotorMode,
+        got: MotorMode,
+    },
+    // No reply to a parameter read within the read timeout
+    Timeout {
+        id: u8,
+        parameter_index: u16,
+    },
+    // Refused until `Supervisor::clear_emergency_stop` is called
+    EmergencyStopped,
+    // The transport's port went away, e.g. an unplugged USB adapter
+    Disconnected(String),
+}
+
+impl fmt::Display for RobstrideError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ActuatorNotFound(id) => write!(f, "Actuator not found: {}", id),
+            Self::TransportNotFound(name) => write!(f, "Transport not found: {}", name),
+            Self::UnexpectedMode { id, expected, got } => write!(
+                f,
+                "Actuator {} is in {:?} mode instead of {:?}, enable it first",
+                id, got, expected
+            ),
+            Self::Timeout {
+                id,
+                parameter_index,
+            } => write!(
+                f,
+                "Timed out reading parameter {:#06x} from actuator {}",
+                parameter_index, id
+            ),
+            Self::EmergencyStopped => write!(
+                f,
+                "Emergency stop is active, call clear_emergency_stop first"
+            ),
+            Self::Disconnected(port) => write!(f, "Port {} disconnected", port),
+        }
+    }
+}
+
+impl std::error::Error for RobstrideError {}
@@ -0,0 +1,155 @@
+use eyre::{Result, WrapErr};
+use num_traits::FromPrimitive;
+use std::io::{ErrorKind, Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{FeedbackFrame, MotorMode};
+
+const MAGIC: &[u8; 4] = b"RSFB";
+const VERSION: u8 = 1;
+
+// Size of one record in bytes
+const RECORD_SIZE: usize = 27;
+
+/// A compact binary log of feedback frames, for long high-rate recordings.
+///
+/// The file starts with the magic bytes `RSFB` and a version byte (1),
+/// followed by fixed-width little-endian records of 27 bytes:
+///
+/// | offset | type | field                                     |
+/// |--------|------|-------------------------------------------|
+/// | 0      | u64  | timestamp, microseconds since the epoch   |
+/// | 8      | u8   | motor ID                                  |
+/// | 9      | f32  | angle (rad)                               |
+/// | 13     | f32  | velocity (rad/s)                          |
+/// | 17     | f32  | torque (Nm)                               |
+/// | 21     | f32  | temperature (Celsius)                     |
+/// | 25     | u16  | flags: bits 0-5 faults, bits 8-9 mode     |
+///
+/// The fault bits are, from bit 0: uncalibrated, hall encoding, magnetic
+/// encoding, over temperature, overcurrent, undervoltage. Values are stored
+/// as given, so log frames returned by `Supervisor::get_feedback` to get
+/// physical units.
+pub struct FeedbackLogWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> FeedbackLogWriter<W> {
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(Self { writer })
+    }
+
+    pub fn write(&mut self, feedback: &FeedbackFrame, timestamp: SystemTime) -> Result<()> {
+        let micros = timestamp
+            .duration_since(UNIX_EPOCH)
+            .wrap_err("timestamp before the epoch")?
+            .as_micros() as u64;
+
+        let mut record = [0u8; RECORD_SIZE];
+        record[0..8].copy_from_slice(&micros.to_le_bytes());
+        record[8] = feedback.motor_id;
+        record[9..13].copy_from_slice(&feedback.angle.to_le_bytes());
+        record[13..17].copy_from_slice(&feedback.velocity.to_le_bytes());
+        record[17..21].copy_from_slice(&feedback.torque.to_le_bytes());
+        record[21..25].copy_from_slice(&feedback.temperature.to_le_bytes());
+        record[25..27].copy_from_slice(&encode_flags(feedback).to_le_bytes());
+
+        self.writer.write_all(&record)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads back a log written by `FeedbackLogWriter`, one sample at a time.
+pub struct FeedbackLogReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> FeedbackLogReader<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; 5];
+        reader
+            .read_exact(&mut header)
+            .wrap_err("failed to read feedback log header")?;
+        if &header[0..4] != MAGIC {
+            return Err(eyre::eyre!("Not a feedback log"));
+        }
+        if header[4] != VERSION {
+            return Err(eyre::eyre!(
+                "Unsupported feedback log version {}",
+                header[4]
+            ));
+        }
+        Ok(Self { reader })
+    }
+
+    /// Returns the next sample, or `None` at the end of the log.
+    pub fn read(&mut self) -> Result<Option<(FeedbackFrame, SystemTime)>> {
+        let mut record = [0u8; RECORD_SIZE];
+        let mut filled = 0;
+        while filled < RECORD_SIZE {
+            match self.reader.read(&mut record[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(eyre::eyre!("Truncated feedback log record")),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let micros = u64::from_le_bytes(record[0..8].try_into().unwrap());
+        let f32_at =
+            |offset: usize| f32::from_le_bytes(record[offset..offset + 4].try_into().unwrap());
+        let flags = u16::from_le_bytes(record[25..27].try_into().unwrap());
+
+        let feedback = FeedbackFrame {
+            angle: f32_at(9),
+            velocity: f32_at(13),
+            torque: f32_at(17),
+            temperature: f32_at(21),
+            fault_uncalibrated: flags & (1 << 0) != 0,
+            fault_hall_encoding: flags & (1 << 1) != 0,
+            fault_magnetic_encoding: flags & (1 << 2) != 0,
+            fault_over_temperature: flags & (1 << 3) != 0,
+            fault_overcurrent: flags & (1 << 4) != 0,
+            fault_undervoltage: flags & (1 << 5) != 0,
+            mode: MotorMode::from_u16((flags >> 8) & 0x3).unwrap_or(MotorMode::Reset),
+            motor_id: record[8],
+        };
+        Ok(Some((feedback, UNIX_EPOCH + Duration::from_micros(micros))))
+    }
+}
+
+impl<R: Read> Iterator for FeedbackLogReader<R> {
+    type Item = Result<(FeedbackFrame, SystemTime)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+fn encode_flags(feedback: &FeedbackFrame) -> u16 {
+    let faults = [
+        feedback.fault_uncalibrated,
+        feedback.fault_hall_encoding,
+        feedback.fault_magnetic_encoding,
+        feedback.fault_over_temperature,
+        feedback.fault_overcurrent,
+        feedback.fault_undervoltage,
+    ];
+    let fault_bits = faults
+        .iter()
+        .enumerate()
+        .fold(0u16, |bits, (i, &fault)| bits | ((fault as u16) << i));
+    fault_bits | ((feedback.mode as u16) << 8)
+}
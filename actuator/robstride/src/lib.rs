@@ -1,13 +1,24 @@
 mod actuator;
 mod actuator_types;
 mod actuators;
+mod error;
+mod feedback_log;
 mod protocol;
+mod recording;
 mod supervisor;
+mod trajectory;
 mod transport;
 
-pub use actuator::{Actuator, Command, CommandData, TypedCommandData};
+pub use actuator::{Actuator, Command, CommandData, TypedCommandData, TypedFeedbackData};
 pub use actuator_types::*;
 pub use actuators::*;
-pub use protocol::Protocol;
+pub use error::RobstrideError;
+pub use feedback_log::{FeedbackLogReader, FeedbackLogWriter};
+pub use protocol::{FrameCallback, Protocol};
+pub use recording::{RecordKind, RecordedSample, Recorder, Recording};
 pub use supervisor::*;
-pub use transport::{CH341Transport, SocketCanTransport, StubTransport, Transport, TransportType};
+pub use trajectory::Trajectory;
+pub use transport::{
+    CH341Decoder, CH341Transport, CanPacket, FlowControl, MockTransport, RxStats, SerialConfig,
+    SocketCanTransport, StopBits, StubTransport, Transport, TransportType,
+};
@@ -3,7 +3,7 @@ use robstride::{
     Supervisor, TransportType,
 };
 use std::time::Duration;
-use tracing::{debug, error, info};
+use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
@@ -65,7 +65,7 @@ async fn main() -> eyre::Result<()> {
                 ..Default::default()
             },
         )
-        .await;
+        .await?;
 
     // Configure actuator 1
     let config = ControlConfig {
@@ -130,13 +130,8 @@ async fn main() -> eyre::Result<()> {
 
     // Clean shutdown
     info!("Shutting down supervisor...");
+    supervisor.shutdown().await?;
     supervisor_handle.abort();
-    // for id in &discovered_ids {
-    //     if let Err(e) = supervisor.disable(*id, false).await {
-    //         error!("Failed to disable actuator {}: {}", id, e);
-    //     }
-    //     debug!("Disabled actuator {}", id);
-    // }
 
     Ok(())
 }
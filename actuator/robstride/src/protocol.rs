@@ -1,18 +1,18 @@
-use crate::transport::{Transport, TransportType};
+use crate::transport::{CanPacket, RxStats, Transport, TransportType};
 use eyre::Error;
 use std::sync::Arc;
 use tracing::trace;
 
+/// Callback invoked with the ID and data of every received frame
+pub type FrameCallback = Arc<dyn Fn(u32, &[u8]) + Send + Sync + 'static>;
+
 pub struct Protocol {
     transport: TransportType,
-    callback: Arc<dyn Fn(u32, Vec<u8>) + Send + Sync + 'static>,
+    callback: FrameCallback,
 }
 
 impl Protocol {
-    pub fn new(
-        transport: TransportType,
-        callback: Arc<dyn Fn(u32, Vec<u8>) + Send + Sync + 'static>,
-    ) -> Self {
+    pub fn new(transport: TransportType, callback: FrameCallback) -> Self {
         Self {
             transport,
             callback,
@@ -30,24 +30,33 @@ impl Protocol {
         self.transport.send(id, data).await
     }
 
-    pub async fn recv(&mut self) -> Result<(u32, Vec<u8>), Error> {
-        let (id, data) = self.transport.recv().await?;
+    pub async fn recv(&mut self) -> Result<CanPacket, Error> {
+        let packet = self.transport.recv().await?;
         trace!(
             "recv {}:{} {:x}: {:02x?}",
             self.transport.kind(),
             self.transport.port(),
-            id,
-            data
+            packet.id,
+            packet.data()
         );
 
-        (self.callback)(id, data.clone());
+        (self.callback)(packet.id, packet.data());
+
+        Ok(packet)
+    }
+
+    pub async fn flush_input(&mut self) -> Result<(), Error> {
+        trace!("flush {}:{}", self.transport.kind(), self.transport.port());
+        self.transport.flush_input().await
+    }
 
-        Ok((id, data))
+    pub fn rx_stats(&self) -> RxStats {
+        self.transport.rx_stats()
     }
 
     pub async fn process_incoming(&mut self) -> Result<(), Error> {
         loop {
-            let (_id, _data) = self.recv().await?;
+            let _packet = self.recv().await?;
         }
     }
 }
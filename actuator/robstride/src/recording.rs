@@ -0,0 +1,212 @@
+use eyre::{Result, WrapErr};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+const HEADER: &str = "time,kind,id,position,velocity,torque,kp,kd";
+
+/// What a recorded row holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordKind {
+    // The target the run loop sent, with the gains
+    Target,
+    // Feedback received from the actuator; kp and kd are zero
+    Feedback,
+}
+
+/// One row of a recording. Values are in radians, rad/s and Nm, with angles
+/// normalized like `Supervisor::get_feedback`, so targets can be passed back
+/// to `Supervisor::command` as is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordedSample {
+    // Seconds since the recording started
+    pub time: f32,
+    pub kind: RecordKind,
+    pub id: u8,
+    pub position: f32,
+    pub velocity: f32,
+    pub torque: f32,
+    pub kp: f32,
+    pub kd: f32,
+}
+
+/// Writes the targets and feedback of a session as CSV, with the header
+/// `time,kind,id,position,velocity,torque,kp,kd` and `kind` either `target`
+/// or `feedback`. Attach one with `Supervisor::start_recording`.
+pub struct Recorder {
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .wrap_err_with(|| format!("failed to create recording {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{}", HEADER)?;
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    /// Seconds since the recorder was created.
+    pub fn elapsed(&self) -> f32 {
+        self.start.elapsed().as_secs_f32()
+    }
+
+    pub fn write(&mut self, sample: &RecordedSample) -> Result<()> {
+        let kind = match sample.kind {
+            RecordKind::Target => "target",
+            RecordKind::Feedback => "feedback",
+        };
+        writeln!(
+            self.writer,
+            "{:.6},{},{},{},{},{},{},{}",
+            sample.time,
+            kind,
+            sample.id,
+            sample.position,
+            sample.velocity,
+            sample.torque,
+            sample.kp,
+            sample.kd
+        )?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// A recording written by `Recorder`, read back for analysis or for
+/// `Supervisor::replay_recording`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    samples: Vec<RecordedSample>,
+}
+
+impl Recording {
+    pub fn from_csv(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read recording {}", path.display()))?;
+        Self::parse_csv(&contents)
+    }
+
+    pub fn parse_csv(contents: &str) -> Result<Self> {
+        let mut lines = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+
+        let header = lines
+            .next()
+            .ok_or_else(|| eyre::eyre!("Recording is empty"))?;
+        if header != HEADER {
+            return Err(eyre::eyre!("Unexpected recording header: {:?}", header));
+        }
+
+        let mut samples = Vec::new();
+        for (row, line) in lines.enumerate() {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != 8 {
+                return Err(eyre::eyre!(
+                    "Recording row {} has {} columns, expected 8",
+                    row + 1,
+                    fields.len()
+                ));
+            }
+            let kind = match fields[1] {
+                "target" => RecordKind::Target,
+                "feedback" => RecordKind::Feedback,
+                other => {
+                    return Err(eyre::eyre!(
+                        "invalid kind in recording row {}: {:?}",
+                        row + 1,
+                        other
+                    ))
+                }
+            };
+            let id = fields[2]
+                .parse::<u8>()
+                .wrap_err_with(|| format!("invalid actuator ID in recording row {}", row + 1))?;
+            let values = [0, 3, 4, 5, 6, 7]
+                .iter()
+                .map(|&column| fields[column].parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .wrap_err_with(|| format!("invalid value in recording row {}", row + 1))?;
+
+            samples.push(RecordedSample {
+                time: values[0],
+                kind,
+                id,
+                position: values[1],
+                velocity: values[2],
+                torque: values[3],
+                kp: values[4],
+                kd: values[5],
+            });
+        }
+
+        Ok(Self { samples })
+    }
+
+    pub fn samples(&self) -> &[RecordedSample] {
+        &self.samples
+    }
+
+    /// The recorded targets, in the order they were written.
+    pub fn targets(&self) -> impl Iterator<Item = &RecordedSample> {
+        self.samples
+            .iter()
+            .filter(|sample| sample.kind == RecordKind::Target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Recordings round-trip the ID and all five control values
+    #[test]
+    fn recordings_round_trip_through_csv() {
+        let samples = [
+            RecordedSample {
+                time: 0.0,
+                kind: RecordKind::Target,
+                id: 3,
+                position: -1.25,
+                velocity: 0.5,
+                torque: 2.0,
+                kp: 40.0,
+                kd: 1.5,
+            },
+            RecordedSample {
+                time: 0.01,
+                kind: RecordKind::Feedback,
+                id: 3,
+                position: -1.2,
+                velocity: 0.45,
+                torque: 1.9,
+                kp: 0.0,
+                kd: 0.0,
+            },
+        ];
+        let path =
+            std::env::temp_dir().join(format!("recording-round-trip-{}.csv", std::process::id()));
+        let mut recorder = Recorder::create(&path).unwrap();
+        for sample in &samples {
+            recorder.write(sample).unwrap();
+        }
+        recorder.flush().unwrap();
+        let recording = Recording::from_csv(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(recording.samples(), samples);
+        assert_eq!(recording.targets().count(), 1);
+    }
+}
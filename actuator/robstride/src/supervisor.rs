@@ -1,7 +1,14 @@
+use async_trait::async_trait;
 use eyre::Result;
-use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use futures::FutureExt;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::{mpsc, RwLock};
 use tokio::time;
 use tracing::{debug, error, info, trace, warn};
@@ -9,15 +16,22 @@ use tracing::{debug, error, info, trace, warn};
 use crate::{
     actuator::{normalize_value, TypedCommandData, TypedFeedbackData},
     actuator_types::ActuatorConfiguration,
-    robstride00::{RobStride00, RobStride00Command, RobStride00Feedback, RobStride00Parameter},
-    robstride01::{RobStride01, RobStride01Command, RobStride01Feedback, RobStride01Parameter},
-    robstride02::{RobStride02, RobStride02Command, RobStride02Feedback, RobStride02Parameter},
-    robstride03::{RobStride03, RobStride03Command, RobStride03Feedback, RobStride03Parameter},
+    error::RobstrideError,
+    protocol::FrameCallback,
+    recording::{RecordKind, RecordedSample, Recorder, Recording},
+    robstride00::{RobStride00, RobStride00Command, RobStride00Feedback},
+    robstride01::{RobStride01, RobStride01Command, RobStride01Feedback},
+    robstride02::{RobStride02, RobStride02Command, RobStride02Feedback},
+    robstride03::{RobStride03, RobStride03Command, RobStride03Feedback},
     robstride04::{RobStride04, RobStride04Command, RobStride04Feedback, RobStride04Parameter},
-    transport::TransportType,
+    trajectory::Trajectory,
+    transport::{Transport, TransportType},
     Actuator, Command, ControlCommand, FeedbackFrame, Frame, Protocol, TxCommand,
 };
-use crate::{ActuatorType, FaultFeedback};
+use crate::{
+    ActuatorMeasurementLimits, ActuatorParameter, ActuatorType, FaultFeedback, MotorMode, RunMode,
+};
+use num_traits::FromPrimitive;
 
 // Add the StateUpdate enum at the top of the file
 #[derive(Debug)]
@@ -25,8 +39,30 @@ enum StateUpdate {
     Feedback(FeedbackFrame),
     ObtainID(u8),
     Fault(FaultFeedback),
+    ParameterRead {
+        motor_id: u8,
+        parameter_index: u16,
+        data: u32,
+    },
 }
 
+// How long to wait for a parameter read reply
+const PARAMETER_READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+// How long `enable_all` waits for feedback after each enable attempt
+const ENABLE_TIMEOUT: Duration = Duration::from_millis(100);
+
+// Interval between commands while ramping down on disable
+const SHUTDOWN_RAMP_STEP: Duration = Duration::from_millis(10);
+
+// How many times `zero` sends the zero command before giving up, and how far
+// from zero (rad) the position may read afterwards
+const ZERO_ATTEMPTS: usize = 3;
+const ZERO_TOLERANCE: f32 = 0.05;
+
+// How long `change_id` waits for the actuator to announce its new ID
+const CHANGE_ID_TIMEOUT: Duration = Duration::from_millis(100);
+
 // Store the latest feedback with timestamp
 #[derive(Clone, Debug)]
 pub struct ActuatorState {
@@ -39,8 +75,303 @@ pub struct ActuatorState {
     pub control_command: ControlCommand,
     pub configuration: ActuatorConfiguration,
     pub messages_received: u64,
+    pub command_failures: u64,
     pub half_revolutions: i32,
     pub actuator_type: ActuatorType,
+    pub parameters: HashMap<u16, ParameterReading>,
+    pub calibration_skips: u64,
+    // Run mode confirmed by reading it back, None until set
+    pub run_mode: Option<RunMode>,
+    // Mode of the last command frame sent, None until one is sent
+    pub last_command_mode: Option<RunMode>,
+    // Whether the run loop's last attempt to send a command failed
+    pub last_send_failed: bool,
+    pub oscillating: bool,
+    // Feedback timed out and hasn't resumed since, so the disconnect
+    // callback runs once per dropout
+    pub disconnected: bool,
+    pub soft_limits: SoftLimits,
+    // Move in progress from `move_to`, stepped by the run loop
+    slew: Option<Slew>,
+    // `disable` is ramping the actuator down and sends its commands itself
+    ramping_down: bool,
+    // Parameters written by `position_control`, `speed_control` and
+    // `current_control`, by index, for `reapply_config` to write again
+    written_parameters: BTreeMap<u16, f32>,
+    // The drive restarted, so the run loop writes its runtime configuration
+    // again on the next cycle
+    reapply_pending: bool,
+    // Times the velocity reversed within the oscillation window
+    velocity_reversals: VecDeque<Instant>,
+    last_velocity_sign: f32,
+    // When the target was last set, for target extrapolation
+    target_time: Instant,
+    // `target_time` of the target that went stale, see `max_target_age`
+    stale_target: Option<Instant>,
+    // When the actuator was last enabled, to tell a stale reset mode report
+    // from one sent after enabling
+    enable_requested: SystemTime,
+}
+
+impl ActuatorState {
+    fn new(actuator_type: ActuatorType, configuration: ActuatorConfiguration) -> Self {
+        Self {
+            feedback: None,
+            last_feedback: SystemTime::now(),
+            last_command: SystemTime::now(),
+            ready: false,
+            enabled: false,
+            control_config: ControlConfig {
+                kp: 0.0,
+                kd: 0.0,
+                max_torque: None,
+                max_velocity: None,
+                max_current: None,
+            },
+            control_command: ControlCommand {
+                target_angle: 0.0,
+                target_velocity: 0.0,
+                kp: 0.0,
+                kd: 0.0,
+                torque: 0.0,
+            },
+            configuration,
+            messages_received: 0,
+            command_failures: 0,
+            half_revolutions: 0,
+            actuator_type,
+            parameters: HashMap::new(),
+            calibration_skips: 0,
+            run_mode: None,
+            last_command_mode: None,
+            last_send_failed: false,
+            oscillating: false,
+            disconnected: false,
+            soft_limits: SoftLimits::default(),
+            slew: None,
+            ramping_down: false,
+            written_parameters: BTreeMap::new(),
+            reapply_pending: false,
+            velocity_reversals: VecDeque::new(),
+            last_velocity_sign: 0.0,
+            target_time: Instant::now(),
+            stale_target: None,
+            enable_requested: SystemTime::UNIX_EPOCH,
+        }
+    }
+
+    // Counts velocity reversals in the feedback to detect sustained oscillation
+    fn track_oscillation(&mut self, feedback: &FeedbackFrame) {
+        let limits = self.configuration.oscillation_limits;
+        let velocity = typed_feedback(self.actuator_type, feedback.clone()).velocity_rads();
+        let now = Instant::now();
+
+        if velocity.abs() >= limits.min_velocity {
+            let sign = velocity.signum();
+            if self.last_velocity_sign != 0.0 && sign != self.last_velocity_sign {
+                self.velocity_reversals.push_back(now);
+            }
+            self.last_velocity_sign = sign;
+        }
+        while self
+            .velocity_reversals
+            .front()
+            .is_some_and(|&reversal| now.duration_since(reversal) > limits.window)
+        {
+            self.velocity_reversals.pop_front();
+        }
+
+        // Two reversals per cycle
+        let cycles = self.velocity_reversals.len() as f32 / 2.0;
+        self.oscillating = cycles >= limits.min_frequency_hz * limits.window.as_secs_f32();
+    }
+
+    // Sets the target from a position (rad, normalized to one turn),
+    // velocity (rad/s) and torque (Nm), keeping the configured gains.
+    // Returns the target after clamping to the soft limits and the model's
+    // range, in the same units.
+    fn set_target(&mut self, position: f32, velocity: f32, torque: f32) -> (f32, f32, f32) {
+        self.slew = None;
+        let limits = self.actuator_type.limits();
+        let (_, velocity, torque) = self.soft_limits.apply(position, velocity, torque);
+        let velocity = velocity.clamp(limits.min_velocity, limits.max_velocity);
+        let torque = torque.clamp(limits.min_torque, limits.max_torque);
+        let (applied, position) = self.limit_position(position);
+
+        let cmd = match self.actuator_type {
+            ActuatorType::RobStride00 => RobStride00Command {
+                target_angle_rad: position,
+                target_velocity_rads: velocity,
+                torque_nm: torque,
+                ..Default::default()
+            }
+            .to_control_command(),
+            ActuatorType::RobStride01 => RobStride01Command {
+                target_angle_rad: position,
+                target_velocity_rads: velocity,
+                torque_nm: torque,
+                ..Default::default()
+            }
+            .to_control_command(),
+            ActuatorType::RobStride02 => RobStride02Command {
+                target_angle_rad: position,
+                target_velocity_rads: velocity,
+                torque_nm: torque,
+                ..Default::default()
+            }
+            .to_control_command(),
+            ActuatorType::RobStride03 => RobStride03Command {
+                target_angle_rad: position,
+                target_velocity_rads: velocity,
+                torque_nm: torque,
+                ..Default::default()
+            }
+            .to_control_command(),
+            ActuatorType::RobStride04 => RobStride04Command {
+                target_angle_rad: position,
+                target_velocity_rads: velocity,
+                torque_nm: torque,
+                ..Default::default()
+            }
+            .to_control_command(),
+        };
+
+        self.control_command.target_angle = cmd.target_angle;
+        self.control_command.target_velocity = cmd.target_velocity;
+        self.control_command.torque = cmd.torque;
+        self.target_time = Instant::now();
+        (applied, velocity, torque)
+    }
+
+    // Clamps a target position (rad, normalized to one turn) to the soft
+    // limits and the model's angle range. Returns it in the same units and
+    // as the absolute angle for the drive.
+    fn limit_position(&self, position: f32) -> (f32, f32) {
+        let limits = self.actuator_type.limits();
+        let (position, _, _) = self.soft_limits.apply(position, 0.0, 0.0);
+        let target = denormalize_radians(position, self.half_revolutions);
+        let absolute = target.clamp(limits.min_angle, limits.max_angle);
+        if absolute == target {
+            (position, absolute)
+        } else {
+            (normalize_radians(absolute).0, absolute)
+        }
+    }
+
+    // Clamps an absolute target position (rad) to the soft position limits,
+    // which apply to the angle normalized to one turn
+    fn soft_limit_position(&self, position: f32) -> f32 {
+        let Some((min, max)) = self.soft_limits.position else {
+            return position;
+        };
+        let normalized = normalize_radians(position).0;
+        let clamped = normalized.clamp(min, max);
+        if clamped == normalized {
+            position
+        } else {
+            denormalize_radians(clamped, self.half_revolutions)
+        }
+    }
+
+    // Clamps a command in the drive's normalized units to the soft limits
+    fn soft_limit_command(&self, cmd: &mut ControlCommand) {
+        let limits = self.actuator_type.limits();
+        if self.soft_limits.position.is_some() {
+            let position = typed_command(self.actuator_type, cmd.clone()).target_angle_rad();
+            let limited = self.soft_limit_position(position);
+            if limited != position {
+                cmd.target_angle =
+                    normalize_value(limited, limits.min_angle, limits.max_angle, -100.0, 100.0);
+            }
+        }
+        if let Some(max) = self.soft_limits.max_velocity {
+            let max = normalize_value(max, limits.min_velocity, limits.max_velocity, -100.0, 100.0);
+            cmd.target_velocity = cmd.target_velocity.clamp(-max, max);
+        }
+        if let Some(max) = self.soft_limits.max_torque {
+            let max = normalize_value(max, limits.min_torque, limits.max_torque, -100.0, 100.0);
+            cmd.torque = cmd.torque.clamp(-max, max);
+        }
+    }
+
+    fn set_gains(&mut self, kp: f32, kd: f32) {
+        let limits = self.actuator_type.limits();
+        self.control_config.kp = kp;
+        self.control_config.kd = kd;
+        self.control_command.kp = normalize_value(kp, limits.min_kp, limits.max_kp, 0.0, 100.0);
+        self.control_command.kd = normalize_value(kd, limits.min_kd, limits.max_kd, 0.0, 100.0);
+    }
+
+    // Steps the target position toward the slew target by at most
+    // max_velocity times the time since the last step
+    fn step_slew(&mut self) {
+        let Some(slew) = self.slew.as_mut() else {
+            return;
+        };
+        let now = Instant::now();
+        let max_step = slew.max_velocity * (now - slew.last_step).as_secs_f32();
+        slew.last_step = now;
+
+        let limits = self.actuator_type.limits();
+        let current = normalize_value(
+            self.control_command.target_angle,
+            -100.0,
+            100.0,
+            limits.min_angle,
+            limits.max_angle,
+        );
+        let step = (slew.target - current).clamp(-max_step, max_step);
+        let arrived = step == slew.target - current;
+        let velocity = if arrived {
+            0.0
+        } else {
+            slew.max_velocity * step.signum()
+        };
+        let target = if arrived { slew.target } else { current + step };
+
+        self.control_command.target_angle =
+            normalize_value(target, limits.min_angle, limits.max_angle, -100.0, 100.0);
+        self.control_command.target_velocity = normalize_value(
+            velocity,
+            limits.min_velocity,
+            limits.max_velocity,
+            -100.0,
+            100.0,
+        );
+        self.target_time = now;
+        if arrived {
+            self.slew = None;
+        }
+    }
+
+    // The target as sent to the drive, with the configured torque sign
+    fn outgoing_command(&self) -> ControlCommand {
+        let mut cmd = self.control_command.clone();
+        cmd.torque *= self.configuration.torque_sign;
+        cmd
+    }
+
+    // Whether the actuator reported reset mode since it was last enabled, so
+    // it ignores commands. False until there is feedback.
+    fn not_started(&self) -> bool {
+        self.feedback
+            .as_ref()
+            .is_some_and(|feedback| feedback.mode == MotorMode::Reset)
+            && self.last_feedback >= self.enable_requested
+    }
+}
+
+// Latest value read back for a parameter
+#[derive(Clone, Copy, Debug)]
+pub struct ParameterReading {
+    pub data: u32,
+    pub received: SystemTime,
+}
+
+impl ParameterReading {
+    pub fn as_f32(&self) -> f32 {
+        f32::from_bits(self.data)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -52,14 +383,357 @@ pub struct ControlConfig {
     pub max_current: Option<f32>,
 }
 
+// Target (rad, as sent to the drive) and velocity limit (rad/s) of a move
+// started by `move_to`
+#[derive(Clone, Copy, Debug)]
+struct Slew {
+    target: f32,
+    max_velocity: f32,
+    last_step: Instant,
+}
+
+/// Per-actuator envelope, tighter than the model's range. Targets are
+/// clamped to it when they are set, and the run loop clamps each command
+/// again as it sends it, after extrapolation and gravity compensation.
+/// Positions are in radians, normalized like `Supervisor::get_feedback`;
+/// velocity (rad/s) and torque (Nm) limits apply to the magnitude. None
+/// leaves that target unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SoftLimits {
+    pub position: Option<(f32, f32)>,
+    pub max_velocity: Option<f32>,
+    pub max_torque: Option<f32>,
+}
+
+impl SoftLimits {
+    fn apply(&self, position: f32, velocity: f32, torque: f32) -> (f32, f32, f32) {
+        let position = match self.position {
+            Some((min, max)) => position.clamp(min, max),
+            None => position,
+        };
+        let velocity = match self.max_velocity {
+            Some(max) => velocity.clamp(-max, max),
+            None => velocity,
+        };
+        let torque = match self.max_torque {
+            Some(max) => torque.clamp(-max, max),
+            None => torque,
+        };
+        (position, velocity, torque)
+    }
+}
+
+/// Target and latest feedback of one actuator, taken at the same instant.
+/// Values are in radians, rad/s and Nm, with angles normalized like
+/// `Supervisor::get_feedback`.
+#[derive(Debug, Clone)]
+pub struct ActuatorSnapshot {
+    pub id: u8,
+    pub enabled: bool,
+    pub target_angle: f32,
+    pub target_velocity: f32,
+    pub target_torque: f32,
+    pub kp: f32,
+    pub kd: f32,
+    pub feedback: Option<FeedbackFrame>,
+    // Time since the feedback was received
+    pub feedback_age: Option<Duration>,
+    // The feedback is older than the feedback timeout, e.g. the actuator
+    // dropped off the bus, and is the last value it reported
+    pub feedback_stale: bool,
+}
+
+impl ActuatorSnapshot {
+    pub fn target_angle_deg(&self) -> f32 {
+        self.target_angle.to_degrees()
+    }
+
+    /// Target velocity in degrees per second.
+    pub fn target_velocity_degs(&self) -> f32 {
+        self.target_velocity.to_degrees()
+    }
+
+    /// Latest feedback position in degrees, None without feedback.
+    pub fn angle_deg(&self) -> Option<f32> {
+        self.feedback
+            .as_ref()
+            .map(|feedback| feedback.angle.to_degrees())
+    }
+
+    /// Latest feedback velocity in degrees per second, None without feedback.
+    pub fn velocity_degs(&self) -> Option<f32> {
+        self.feedback
+            .as_ref()
+            .map(|feedback| feedback.velocity.to_degrees())
+    }
+}
+
+/// What happened to the last command the run loop sent to each actuator, see
+/// `Supervisor::command_report`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandReport {
+    // Feedback arrived after the command
+    pub responded: Vec<u8>,
+    // Sent, but no feedback has arrived since
+    pub no_response: Vec<u8>,
+    // Failed to send at the transport
+    pub send_errors: Vec<u8>,
+}
+
+/// Round-trip statistics of one actuator's link, see
+/// `Supervisor::link_quality`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkQuality {
+    pub samples: u32,
+    pub timeouts: u32,
+    pub mean_latency: Duration,
+    pub max_latency: Duration,
+}
+
+impl LinkQuality {
+    pub fn success_rate(&self) -> f32 {
+        if self.samples == 0 {
+            return 0.0;
+        }
+        (self.samples - self.timeouts) as f32 / self.samples as f32
+    }
+}
+
+/// Why the supervisor is or isn't ready to be commanded, see
+/// `Supervisor::readiness_report`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReadinessReport {
+    // Whether the run loop is executing, false again once it has returned
+    pub running: bool,
+    pub disconnected_transports: Vec<String>,
+    // Actuators that haven't reported feedback yet
+    pub waiting_for_feedback: Vec<u8>,
+    pub calibrating: Vec<u8>,
+    pub faulted: Vec<u8>,
+}
+
+impl ReadinessReport {
+    pub fn is_ready(&self) -> bool {
+        self.running
+            && self.disconnected_transports.is_empty()
+            && self.waiting_for_feedback.is_empty()
+            && self.calibrating.is_empty()
+            && self.faulted.is_empty()
+    }
+}
+
+/// Frame counts of one transport since it was added, see
+/// `Supervisor::bus_stats`. A rising error count is an early sign of a baud
+/// rate mismatch or electrical noise.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BusStats {
+    pub frames_sent: u64,
+    pub send_errors: u64,
+    pub frames_received: u64,
+    // Received frames that are not a known RobStride message
+    pub decode_errors: u64,
+    // Framing errors of byte-stream transports, see `RxStats`
+    pub invalid_frames: u64,
+    pub discarded_bytes: u64,
+    pub resyncs: u64,
+}
+
 pub struct TransportHandler {
-    #[allow(unused)]
     protocol: Protocol,
     tx: mpsc::Sender<TxCommand>,
+    // Set once the receive loop stops, e.g. because the adapter was unplugged
+    disconnected: Arc<RwLock<bool>>,
+    // Updated from the frame callback as well, so not behind an async lock
+    stats: Arc<Mutex<BusStats>>,
     #[allow(unused)]
     rx: mpsc::Receiver<TxCommand>,
 }
 
+/// Maps the actuator positions (radians) to feedforward torques (Nm), see
+/// `Supervisor::set_gravity_compensation`.
+pub type GravityCompensation = Box<dyn Fn(&HashMap<u8, f32>) -> HashMap<u8, f32> + Send + Sync>;
+
+/// Called by the run loop with the ID of an actuator whose feedback just
+/// timed out, see `Supervisor::set_disconnect_callback`.
+pub type DisconnectCallback = Box<dyn Fn(u8) + Send + Sync>;
+
+/// User setup run by `Supervisor::enable` right after an actuator is enabled,
+/// e.g. to write gains or current limits before it is commanded.
+#[async_trait]
+pub trait InitHook: Send + Sync {
+    async fn run(&self, actuator: &dyn Actuator) -> Result<()>;
+}
+
+/// User teardown run by `Supervisor::shutdown` before the actuators are
+/// disabled, e.g. to log the final state or move to a park pose.
+#[async_trait]
+pub trait ShutdownHook: Send + Sync {
+    async fn run(&self, supervisor: &mut Supervisor) -> Result<()>;
+}
+
+// Recorder attached by `start_recording`, with the time of the last
+// feedback written for each actuator so each frame is written once
+struct ActiveRecording {
+    recorder: Recorder,
+    last_feedback: HashMap<u8, SystemTime>,
+}
+
+impl ActiveRecording {
+    fn write(&mut self, actuators: &HashMap<u8, ActuatorRecord>) -> Result<()> {
+        let time = self.recorder.elapsed();
+        let mut ids: Vec<u8> = actuators.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            let state = &actuators[&id].state;
+            if state.enabled {
+                let target = typed_command(state.actuator_type, state.control_command.clone());
+                self.recorder.write(&RecordedSample {
+                    time,
+                    kind: RecordKind::Target,
+                    id,
+                    position: normalize_radians(target.target_angle_rad()).0,
+                    velocity: target.target_velocity_rads(),
+                    torque: target.torque_nm(),
+                    kp: target.kp(),
+                    kd: target.kd(),
+                })?;
+            }
+
+            let Some(feedback) = state.feedback.clone() else {
+                continue;
+            };
+            if self
+                .last_feedback
+                .get(&id)
+                .is_some_and(|&written| written >= state.last_feedback)
+            {
+                continue;
+            }
+            self.last_feedback.insert(id, state.last_feedback);
+            let feedback = feedback_in_units(state.actuator_type, feedback);
+            self.recorder.write(&RecordedSample {
+                time,
+                kind: RecordKind::Feedback,
+                id,
+                position: normalize_radians(feedback.angle).0,
+                velocity: feedback.velocity,
+                torque: feedback.torque,
+                kp: 0.0,
+                kd: 0.0,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+// Trajectory being played back by the run loop
+struct Playback {
+    trajectory: Trajectory,
+    start: Instant,
+    looping: bool,
+}
+
+// Time of the last target set by the user, and whether it is older than the
+// timeout set by `set_command_watchdog`
+struct CommandWatchdog {
+    timeout: Option<Duration>,
+    last_target: Instant,
+    tripped: bool,
+}
+
+impl CommandWatchdog {
+    // Rechecks the watchdog, returning whether it is tripped
+    fn check(&mut self) -> bool {
+        let tripped = self
+            .timeout
+            .is_some_and(|timeout| self.last_target.elapsed() > timeout);
+        if tripped && !self.tripped {
+            warn!("No new target within the command watchdog timeout, braking");
+        } else if !tripped && self.tripped {
+            info!("New target received, command watchdog cleared");
+        }
+        self.tripped = tripped;
+        tripped
+    }
+}
+
+// Global scale applied to kp, kd and torque, ramping linearly from `from` to
+// `to` over `duration`
+struct CommandScale {
+    from: f32,
+    to: f32,
+    start: Instant,
+    duration: Duration,
+}
+
+impl CommandScale {
+    fn value(&self) -> f32 {
+        if self.duration.is_zero() {
+            return self.to;
+        }
+        let t = (self.start.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.0);
+        self.from + (self.to - self.from) * t
+    }
+}
+
+/// Default weight of the newest iteration in the smoothed update rate
+const DEFAULT_UPDATE_RATE_SMOOTHING: f32 = 0.1;
+
+// Progress of the run loop, shared with controller clones
+struct LoopStats {
+    started: Option<Instant>,
+    iterations: u64,
+    last_tick: Option<Instant>,
+    // Update rates in Hz, of the last iteration and exponentially smoothed
+    instantaneous_rate: f32,
+    smoothed_rate: f32,
+    smoothing: f32,
+}
+
+impl Default for LoopStats {
+    fn default() -> Self {
+        Self {
+            started: None,
+            iterations: 0,
+            last_tick: None,
+            instantaneous_rate: 0.0,
+            smoothed_rate: 0.0,
+            smoothing: DEFAULT_UPDATE_RATE_SMOOTHING,
+        }
+    }
+}
+
+impl LoopStats {
+    fn tick(&mut self) {
+        let now = Instant::now();
+        self.iterations += 1;
+        if let Some(last) = self.last_tick {
+            let elapsed = now.duration_since(last).as_secs_f32();
+            if elapsed > 0.0 {
+                self.instantaneous_rate = 1.0 / elapsed;
+                // Start from the first measurement rather than ramping up from 0
+                self.smoothed_rate = if self.smoothed_rate == 0.0 {
+                    self.instantaneous_rate
+                } else {
+                    self.smoothed_rate
+                        + self.smoothing * (self.instantaneous_rate - self.smoothed_rate)
+                };
+            }
+        }
+        self.last_tick = Some(now);
+    }
+}
+
+// Clears the running flag when the run loop returns, also with an error or
+// when its future is dropped
+struct RunningGuard(Arc<AtomicBool>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
 struct ActuatorRecord {
     actuator: Box<dyn Actuator>,
     state: ActuatorState,
@@ -69,8 +743,26 @@ pub struct Supervisor {
     actuators: Arc<RwLock<HashMap<u8, ActuatorRecord>>>,
     transports: Arc<RwLock<HashMap<String, TransportHandler>>>,
     discovered_ids: Arc<RwLock<Vec<u8>>>,
+    playback: Arc<RwLock<Option<Playback>>>,
+    recording: Arc<RwLock<Option<ActiveRecording>>>,
+    loop_stats: Arc<RwLock<LoopStats>>,
+    // Whether `run` is executing on this supervisor or a controller clone
+    running: Arc<AtomicBool>,
+    command_scale: Arc<RwLock<CommandScale>>,
+    command_watchdog: Arc<RwLock<CommandWatchdog>>,
+    gravity_compensation: Arc<RwLock<Option<GravityCompensation>>>,
+    disconnect_callback: Arc<RwLock<Option<DisconnectCallback>>>,
     last_stats_time: SystemTime,
+    stats_interval: Option<Duration>,
     feedback_timeout: Duration,
+    shutdown_ramp: Duration,
+    split_rx_tx: bool,
+    init_hook: Option<Arc<dyn InitHook>>,
+    shutdown_hook: Option<Arc<dyn ShutdownHook>>,
+    // Set by `shutdown`, cleared when an actuator is enabled again
+    shut_down: Arc<RwLock<bool>>,
+    // Set by `emergency_stop`, cleared by `clear_emergency_stop`
+    emergency_stopped: Arc<RwLock<bool>>,
     state_update_tx: mpsc::Sender<StateUpdate>,
 }
 
@@ -109,16 +801,159 @@ fn denormalize_radians(normalized_radians: f32, half_rotations: i32) -> f32 {
     original_degrees.to_radians()
 }
 
+fn typed_command(actuator_type: ActuatorType, cmd: ControlCommand) -> Box<dyn TypedCommandData> {
+    match actuator_type {
+        ActuatorType::RobStride00 => Box::new(RobStride00Command::from_control_command(cmd)),
+        ActuatorType::RobStride01 => Box::new(RobStride01Command::from_control_command(cmd)),
+        ActuatorType::RobStride02 => Box::new(RobStride02Command::from_control_command(cmd)),
+        ActuatorType::RobStride03 => Box::new(RobStride03Command::from_control_command(cmd)),
+        ActuatorType::RobStride04 => Box::new(RobStride04Command::from_control_command(cmd)),
+    }
+}
+
+fn typed_feedback(actuator_type: ActuatorType, frame: FeedbackFrame) -> Box<dyn TypedFeedbackData> {
+    match actuator_type {
+        ActuatorType::RobStride00 => Box::new(RobStride00Feedback::from_feedback_frame(frame)),
+        ActuatorType::RobStride01 => Box::new(RobStride01Feedback::from_feedback_frame(frame)),
+        ActuatorType::RobStride02 => Box::new(RobStride02Feedback::from_feedback_frame(frame)),
+        ActuatorType::RobStride03 => Box::new(RobStride03Feedback::from_feedback_frame(frame)),
+        ActuatorType::RobStride04 => Box::new(RobStride04Feedback::from_feedback_frame(frame)),
+    }
+}
+
+// Converts the normalized feedback values to radians, rad/s and Nm
+fn feedback_in_units(actuator_type: ActuatorType, mut feedback: FeedbackFrame) -> FeedbackFrame {
+    let typed = typed_feedback(actuator_type, feedback.clone());
+    feedback.angle = typed.angle_rad();
+    feedback.velocity = typed.velocity_rads();
+    feedback.torque = typed.torque_nm();
+    feedback
+}
+
+// Counts a send in the transport's stats
+fn record_send(stats: &Mutex<BusStats>, result: Result<()>) {
+    let mut stats = stats.lock().unwrap();
+    match result {
+        Ok(()) => stats.frames_sent += 1,
+        Err(e) => {
+            error!("Transport sender error: {}", e);
+            stats.send_errors += 1;
+        }
+    }
+}
+
+// Commands are ignored in reset mode, so either enable the actuator, if
+// configured to, or fail rather than let the command be silently dropped
+async fn ensure_started(
+    id: u8,
+    record: &mut ActuatorRecord,
+    emergency_stopped: bool,
+) -> Result<()> {
+    if needs_start(id, record, emergency_stopped)? {
+        auto_start(id, record).await?;
+    }
+    Ok(())
+}
+
+// Returns whether the actuator is in reset mode and has to be enabled before
+// it follows commands, failing if it can't be or an emergency stop is active
+fn needs_start(id: u8, record: &ActuatorRecord, emergency_stopped: bool) -> Result<bool> {
+    if emergency_stopped {
+        return Err(RobstrideError::EmergencyStopped.into());
+    }
+    if !record.state.not_started() {
+        return Ok(false);
+    }
+    if !record.state.configuration.auto_start {
+        return Err(RobstrideError::UnexpectedMode {
+            id,
+            expected: MotorMode::Run,
+            got: MotorMode::Reset,
+        }
+        .into());
+    }
+    Ok(true)
+}
+
+async fn auto_start(id: u8, record: &mut ActuatorRecord) -> Result<()> {
+    warn!("Actuator {} is in reset mode, enabling it", id);
+    record.actuator.enable().await?;
+    record.state.enabled = true;
+    record.state.enable_requested = SystemTime::now();
+    Ok(())
+}
+
+// Writes a runtime parameter and remembers it for `reapply_runtime_config`
+async fn write_runtime_parameter(
+    record: &mut ActuatorRecord,
+    index: u16,
+    value: f32,
+) -> Result<()> {
+    record.actuator.set_parameter(index, value).await?;
+    record.state.written_parameters.insert(index, value);
+    Ok(())
+}
+
+// Writes the limits set by `configure`, the run mode set by `set_run_mode`
+// and the gains and targets written by the drive-side control modes to the
+// drive again
+async fn reapply_runtime_config(record: &ActuatorRecord) -> Result<()> {
+    let config = &record.state.control_config;
+    if let Some(max_torque) = config.max_torque {
+        record.actuator.set_max_torque(max_torque).await?;
+    }
+    if let Some(max_velocity) = config.max_velocity {
+        record.actuator.set_max_velocity(max_velocity).await?;
+    }
+    if let Some(max_current) = config.max_current {
+        record.actuator.set_max_current(max_current).await?;
+    }
+    if let Some(run_mode) = record.state.run_mode {
+        record.actuator.set_run_mode(run_mode).await?;
+    }
+    for (&index, &value) in &record.state.written_parameters {
+        record.actuator.set_parameter(index, value).await?;
+    }
+    Ok(())
+}
+
 impl Supervisor {
     pub fn new(feedback_timeout: Duration) -> Result<Self> {
+        if feedback_timeout.is_zero() {
+            return Err(eyre::eyre!("Feedback timeout must be non-zero"));
+        }
         let (state_update_tx, mut state_update_rx) = mpsc::channel(32);
 
         let supervisor = Self {
             actuators: Arc::new(RwLock::new(HashMap::new())),
             transports: Arc::new(RwLock::new(HashMap::new())),
             discovered_ids: Arc::new(RwLock::new(Vec::new())),
+            playback: Arc::new(RwLock::new(None)),
+            recording: Arc::new(RwLock::new(None)),
+            loop_stats: Arc::new(RwLock::new(LoopStats::default())),
+            running: Arc::new(AtomicBool::new(false)),
+            command_scale: Arc::new(RwLock::new(CommandScale {
+                from: 1.0,
+                to: 1.0,
+                start: Instant::now(),
+                duration: Duration::ZERO,
+            })),
+            command_watchdog: Arc::new(RwLock::new(CommandWatchdog {
+                timeout: None,
+                last_target: Instant::now(),
+                tripped: false,
+            })),
+            gravity_compensation: Arc::new(RwLock::new(None)),
+            disconnect_callback: Arc::new(RwLock::new(None)),
             last_stats_time: SystemTime::now(),
+            stats_interval: Some(Duration::from_secs(5)),
             feedback_timeout,
+            shutdown_ramp: Duration::ZERO,
+            split_rx_tx: false,
+            init_hook: None,
+            shutdown_hook: None,
+            shut_down: Arc::new(RwLock::new(false)),
+            emergency_stopped: Arc::new(RwLock::new(false)),
             state_update_tx,
         };
 
@@ -132,6 +967,21 @@ impl Supervisor {
                         StateUpdate::Feedback(feedback) => {
                             let mut actuators_guard = actuators.write().await;
                             if let Some(record) = actuators_guard.get_mut(&feedback.motor_id) {
+                                // Runtime settings are lost when the drive power cycles, seen
+                                // as an enabled actuator dropping to reset mode or feedback
+                                // resuming after a timeout
+                                let restarted =
+                                    record.state.feedback.as_ref().is_some_and(|previous| {
+                                        (record.state.enabled
+                                            && previous.mode != MotorMode::Reset
+                                            && feedback.mode == MotorMode::Reset)
+                                            || record
+                                                .state
+                                                .last_feedback
+                                                .elapsed()
+                                                .is_ok_and(|age| age > feedback_timeout)
+                                    });
+
                                 record.state.feedback = Some(feedback.clone());
                                 record.state.last_feedback = SystemTime::now();
                                 record.state.messages_received += 1;
@@ -146,9 +996,34 @@ impl Supervisor {
                                     record.state.control_command.target_angle = feedback.angle;
                                 }
 
+                                record.state.track_oscillation(&feedback);
+                                if record.state.oscillating
+                                    && record.state.configuration.reduce_gains_on_oscillation
+                                {
+                                    warn!(
+                                        "Actuator {} is oscillating, halving kp and kd",
+                                        feedback.motor_id
+                                    );
+                                    record.state.control_config.kp *= 0.5;
+                                    record.state.control_config.kd *= 0.5;
+                                    record.state.control_command.kp *= 0.5;
+                                    record.state.control_command.kd *= 0.5;
+                                    record.state.velocity_reversals.clear();
+                                }
+
                                 let angle_rad =
                                     RobStride04Feedback::from_feedback_frame(feedback).angle_rad;
                                 record.state.half_revolutions = normalize_radians(angle_rad).1;
+
+                                // Written by the run loop, so feedback isn't held up
+                                // behind the sends
+                                if restarted {
+                                    warn!(
+                                        "Actuator {} restarted, reapplying its configuration",
+                                        record.actuator.id()
+                                    );
+                                    record.state.reapply_pending = true;
+                                }
                             }
                         }
                         StateUpdate::ObtainID(motor_id) => {
@@ -159,7 +1034,23 @@ impl Supervisor {
                             }
                         }
                         StateUpdate::Fault(fault) => {
-                            warn!("Fault received: {:?}", fault);
+                            warn!("Fault received: {}", fault);
+                        }
+                        StateUpdate::ParameterRead {
+                            motor_id,
+                            parameter_index,
+                            data,
+                        } => {
+                            let mut actuators_guard = actuators.write().await;
+                            if let Some(record) = actuators_guard.get_mut(&motor_id) {
+                                record.state.parameters.insert(
+                                    parameter_index,
+                                    ParameterReading {
+                                        data,
+                                        received: SystemTime::now(),
+                                    },
+                                );
+                            }
                         }
                     }
                 }
@@ -174,12 +1065,83 @@ impl Supervisor {
             actuators: self.actuators.clone(),
             transports: self.transports.clone(),
             discovered_ids: self.discovered_ids.clone(),
+            playback: self.playback.clone(),
+            recording: self.recording.clone(),
+            loop_stats: self.loop_stats.clone(),
+            running: self.running.clone(),
+            command_scale: self.command_scale.clone(),
+            command_watchdog: self.command_watchdog.clone(),
+            gravity_compensation: self.gravity_compensation.clone(),
+            disconnect_callback: self.disconnect_callback.clone(),
             last_stats_time: self.last_stats_time,
+            stats_interval: self.stats_interval,
             feedback_timeout: self.feedback_timeout,
+            shutdown_ramp: self.shutdown_ramp,
+            split_rx_tx: self.split_rx_tx,
+            init_hook: self.init_hook.clone(),
+            shutdown_hook: self.shutdown_hook.clone(),
+            shut_down: self.shut_down.clone(),
+            emergency_stopped: self.emergency_stopped.clone(),
             state_update_tx: self.state_update_tx.clone(),
         }
     }
 
+    /// Sets whether transports added afterwards receive and send in separate
+    /// tasks, so commands stream without waiting behind reads and feedback is
+    /// drained continuously. Only applies to full-duplex transports
+    /// (SocketCAN); others keep the default single task, which alternates
+    /// between the two.
+    pub fn set_split_rx_tx(&mut self, enabled: bool) {
+        self.split_rx_tx = enabled;
+    }
+
+    /// Sets how often the run loop logs a summary of message rate, command
+    /// failures and the latest feedback of each actuator, or disables it with
+    /// `None`. Defaults to every 5 seconds. Per-frame logs stay at trace level.
+    /// Set this on the instance that runs the loop.
+    pub fn set_stats_interval(&mut self, interval: Option<Duration>) {
+        self.stats_interval = interval;
+    }
+
+    /// Time since the run loop started, or zero if it hasn't.
+    pub async fn uptime(&self) -> Duration {
+        self.loop_stats
+            .read()
+            .await
+            .started
+            .map_or(Duration::ZERO, |started| started.elapsed())
+    }
+
+    /// Number of run loop iterations since it started.
+    pub async fn iteration_count(&self) -> u64 {
+        self.loop_stats.read().await.iterations
+    }
+
+    /// Run loop rate in Hz, exponentially smoothed over iterations so it is
+    /// steady enough to display. Zero until the loop has run twice.
+    pub async fn update_rate(&self) -> f32 {
+        self.loop_stats.read().await.smoothed_rate
+    }
+
+    /// Run loop rate in Hz from the duration of the last iteration alone.
+    pub async fn instantaneous_update_rate(&self) -> f32 {
+        self.loop_stats.read().await.instantaneous_rate
+    }
+
+    /// Sets the weight, in (0, 1], of the newest iteration in `update_rate`.
+    /// Smaller values are smoother but slower to follow changes; 1 disables
+    /// smoothing. Defaults to 0.1.
+    pub async fn set_update_rate_smoothing(&self, smoothing: f32) -> Result<()> {
+        if !(smoothing > 0.0 && smoothing <= 1.0) {
+            return Err(eyre::eyre!(
+                "Update rate smoothing must be in (0, 1], got {}",
+                smoothing
+            ));
+        }
+        self.loop_stats.write().await.smoothing = smoothing;
+        Ok(())
+    }
+
     pub async fn add_transport(&self, name: String, transport: TransportType) -> Result<()> {
         info!("Adding transport: {}", name);
         let (tx, mut rx) = mpsc::channel(32);
@@ -187,69 +1149,131 @@ impl Supervisor {
         let state_update_tx = self.state_update_tx.clone();
         let name_clone = name.clone();
         let name_for_log = name_clone.clone();
+        let stats = Arc::new(Mutex::new(BusStats::default()));
+        let callback_stats = stats.clone();
 
         // Create callback for frame processing
-        let frame_callback: Arc<dyn Fn(u32, Vec<u8>) + Send + Sync + 'static> =
-            Arc::new(move |id: u32, data: Vec<u8>| {
-                let cmd = Command::from_can_packet(id, data.clone());
-                trace!(
-                    "Transport callback received: id={:x}, data={:02x?}, cmd={:?}",
-                    id,
-                    data,
-                    cmd
-                );
+        let frame_callback: FrameCallback = Arc::new(move |id: u32, data: &[u8]| {
+            let cmd = match Command::from_can_packet(id, data) {
+                Ok(cmd) => cmd,
+                Err(e) => {
+                    debug!("Ignoring frame id={:x}, data={:02x?}: {}", id, data, e);
+                    callback_stats.lock().unwrap().decode_errors += 1;
+                    return;
+                }
+            };
+            trace!(
+                "Transport callback received: id={:x}, data={:02x?}, cmd={:?}",
+                id,
+                data,
+                cmd
+            );
 
-                if let Ok(cmd_frame) = cmd.to_frame() {
-                    match cmd_frame {
-                        Frame::Feedback(feedback) => {
-                            let _ = state_update_tx.try_send(StateUpdate::Feedback(feedback));
-                        }
-                        Frame::ObtainID(oid) => {
-                            let _ = state_update_tx.try_send(StateUpdate::ObtainID(oid.host_id));
-                        }
-                        Frame::Fault(fault) => {
-                            let _ = state_update_tx.try_send(StateUpdate::Fault(fault));
-                        }
-                        _ => trace!("received: {:?}", cmd_frame),
+            if let Ok(cmd_frame) = cmd.to_frame() {
+                match cmd_frame {
+                    Frame::Feedback(feedback) => {
+                        let _ = state_update_tx.try_send(StateUpdate::Feedback(feedback));
                     }
-                } else {
-                    warn!("Failed to parse frame from command: {:?}", cmd);
+                    Frame::ObtainID(oid) => {
+                        let _ = state_update_tx.try_send(StateUpdate::ObtainID(oid.host_id));
+                    }
+                    Frame::Fault(fault) => {
+                        let _ = state_update_tx.try_send(StateUpdate::Fault(fault));
+                    }
+                    Frame::Read(read) => {
+                        // In replies the host ID field carries the motor ID
+                        let _ = state_update_tx.try_send(StateUpdate::ParameterRead {
+                            motor_id: read.host_id,
+                            parameter_index: read.parameter_index,
+                            data: read.data,
+                        });
+                    }
+                    _ => trace!("received: {:?}", cmd_frame),
                 }
-            });
+            } else {
+                warn!("Failed to parse frame from command: {:?}", cmd);
+                callback_stats.lock().unwrap().decode_errors += 1;
+            }
+        });
 
         let protocol = Protocol::new(transport.clone(), frame_callback);
         debug!("Created protocol for transport: {}", name);
 
         // Spawn the transport handling task
         let mut protocol_clone = protocol.clone();
-        tokio::spawn(async move {
-            info!("Starting transport handling task for {}", name_clone);
-            loop {
-                tokio::select! {
-                    // Handle incoming messages
-                    recv_result = protocol_clone.recv() => {
-                        match recv_result {
-                            Ok(_) => trace!("Received message successfully"),
-                            Err(e) => {
-                                error!("Transport receiver error: {}", e);
-                                break;
-                            }
+        let disconnected = Arc::new(RwLock::new(false));
+        let disconnected_clone = disconnected.clone();
+        let task_stats = stats.clone();
+        if self.split_rx_tx && transport.full_duplex() {
+            // Receive in a task of its own, so commands go out without
+            // waiting for a pending read
+            let mut rx_protocol = protocol.clone();
+            let rx_stats = stats.clone();
+            let rx_name = name_clone.clone();
+            tokio::spawn(async move {
+                info!("Starting receive task for {}", rx_name);
+                loop {
+                    match rx_protocol.recv().await {
+                        Ok(_) => rx_stats.lock().unwrap().frames_received += 1,
+                        Err(e) => {
+                            error!("Transport receiver error: {}", e);
+                            *disconnected_clone.write().await = true;
+                            break;
+                        }
+                    }
+                }
+            });
+            tokio::spawn(async move {
+                info!("Starting send task for {}", name_clone);
+                while let Some(cmd) = rx.recv().await {
+                    trace!("Processing outgoing command: {:?}", cmd);
+                    match cmd {
+                        TxCommand::Send { id, data } => {
+                            let result = protocol_clone.send(id, &data).await;
+                            record_send(&task_stats, result);
                         }
                     }
-                    // Handle outgoing messages
-                    Some(cmd) = rx.recv() => {
-                        trace!("Processing outgoing command: {:?}", cmd);
-                        match cmd {
-                            TxCommand::Send { id, data } => {
-                                if let Err(e) = protocol_clone.send(id, &data).await {
-                                    error!("Transport sender error: {}", e);
+                }
+            });
+        } else {
+            if self.split_rx_tx {
+                warn!(
+                    "Transport {} is not full duplex, sending and receiving in one task",
+                    name
+                );
+            }
+            tokio::spawn(async move {
+                info!("Starting transport handling task for {}", name_clone);
+                loop {
+                    tokio::select! {
+                        // Handle incoming messages
+                        recv_result = protocol_clone.recv() => {
+                            match recv_result {
+                                Ok(_) => {
+                                    trace!("Received message successfully");
+                                    task_stats.lock().unwrap().frames_received += 1;
+                                }
+                                Err(e) => {
+                                    error!("Transport receiver error: {}", e);
+                                    *disconnected_clone.write().await = true;
+                                    break;
+                                }
+                            }
+                        }
+                        // Handle outgoing messages
+                        Some(cmd) = rx.recv() => {
+                            trace!("Processing outgoing command: {:?}", cmd);
+                            match cmd {
+                                TxCommand::Send { id, data } => {
+                                    let result = protocol_clone.send(id, &data).await;
+                                    record_send(&task_stats, result);
                                 }
                             }
                         }
                     }
                 }
-            }
-        });
+            });
+        }
 
         let mut transports = self.transports.write().await;
         transports.insert(
@@ -257,6 +1281,8 @@ impl Supervisor {
             TransportHandler {
                 protocol,
                 tx: tx.clone(),
+                disconnected,
+                stats,
                 rx: mpsc::channel(32).1,
             },
         );
@@ -269,45 +1295,65 @@ impl Supervisor {
         let transports = self.transports.read().await;
         let transport = transports
             .get(transport_name)
-            .ok_or_else(|| eyre::eyre!("Transport not found: {}", transport_name))?;
+            .ok_or_else(|| RobstrideError::TransportNotFound(transport_name.to_string()))?;
         Ok(transport.tx.clone())
     }
 
+    /// Returns the frame and error counts of a transport since it was added.
+    pub async fn bus_stats(&self, transport_name: &str) -> Result<BusStats> {
+        let transports = self.transports.read().await;
+        let transport = transports
+            .get(transport_name)
+            .ok_or_else(|| RobstrideError::TransportNotFound(transport_name.to_string()))?;
+        let mut stats = *transport.stats.lock().unwrap();
+        let rx_stats = transport.protocol.rx_stats();
+        stats.invalid_frames = rx_stats.invalid_frames;
+        stats.discarded_bytes = rx_stats.discarded_bytes;
+        stats.resyncs = rx_stats.resyncs;
+        Ok(stats)
+    }
+
+    /// Returns whether the transport is still receiving. Once its receive loop
+    /// has failed, e.g. because the adapter was unplugged, the transport stays
+    /// disconnected and must be added again.
+    pub async fn is_connected(&self, transport_name: &str) -> Result<bool> {
+        let disconnected = {
+            let transports = self.transports.read().await;
+            let transport = transports
+                .get(transport_name)
+                .ok_or_else(|| RobstrideError::TransportNotFound(transport_name.to_string()))?;
+            transport.disconnected.clone()
+        };
+        let disconnected = *disconnected.read().await;
+        Ok(!disconnected)
+    }
+
+    /// Discards unread input on a transport, e.g. stale bytes after the
+    /// adapter was reconnected.
+    pub async fn flush_input(&self, transport_name: &str) -> Result<()> {
+        let mut protocol = {
+            let transports = self.transports.read().await;
+            let transport = transports
+                .get(transport_name)
+                .ok_or_else(|| RobstrideError::TransportNotFound(transport_name.to_string()))?;
+            transport.protocol.clone()
+        };
+        protocol.flush_input().await
+    }
+
     pub async fn add_actuator(
         &self,
         actuator: Box<dyn Actuator>,
         configuration: ActuatorConfiguration,
-    ) {
+    ) -> Result<()> {
+        configuration.validate()?;
+        self.check_command_rate(&configuration)?;
         let actuator_id = actuator.id();
         let actuator_type = actuator.actuator_type();
 
         let record = ActuatorRecord {
             actuator,
-            state: ActuatorState {
-                feedback: None,
-                last_feedback: SystemTime::now(),
-                last_command: SystemTime::now(),
-                ready: false,
-                enabled: false,
-                control_config: ControlConfig {
-                    kp: 0.0,
-                    kd: 0.0,
-                    max_torque: None,
-                    max_velocity: None,
-                    max_current: None,
-                },
-                control_command: ControlCommand {
-                    target_angle: 0.0,
-                    target_velocity: 0.0,
-                    kp: 0.0,
-                    kd: 0.0,
-                    torque: 0.0,
-                },
-                configuration,
-                messages_received: 0,
-                half_revolutions: 0,
-                actuator_type,
-            },
+            state: ActuatorState::new(actuator_type, configuration),
         };
 
         let mut actuators = self.actuators.write().await;
@@ -317,14 +1363,32 @@ impl Supervisor {
             "Added actuator with ID: {} (type: {:?})",
             actuator_id, actuator_type
         );
+        Ok(())
+    }
+
+    /// Returns the IDs of all actuators added to the supervisor, sorted.
+    pub async fn actuator_ids(&self) -> Vec<u8> {
+        let actuators = self.actuators.read().await;
+        let mut ids: Vec<u8> = actuators.keys().copied().collect();
+        ids.sort_unstable();
+        ids
     }
 
+    /// Sends a device ID request to every ID on `transport_name` and adds each
+    /// actuator that answers within 100 ms, using its entry in
+    /// `actuator_configs` or a RobStride04 default. Returns the IDs that
+    /// answered, sorted, including ones that were already added. Frames that
+    /// don't decode are ignored.
     pub async fn scan_bus(
         &mut self,
         host_id: u8,
         transport_name: &str,
         actuator_configs: &[(u8, ActuatorConfiguration)],
     ) -> Result<Vec<u8>> {
+        for (_, config) in actuator_configs {
+            config.validate()?;
+            self.check_command_rate(config)?;
+        }
         let transport_tx = self.get_transport_tx(transport_name).await?;
 
         {
@@ -375,7 +1439,7 @@ impl Supervisor {
             // Process any new IDs
             for id in discovered_ids {
                 let mut actuators = self.actuators.write().await;
-                if !actuators.contains_key(&id) {
+                if let Entry::Vacant(entry) = actuators.entry(id) {
                     let (actuator, configuration): (Box<dyn Actuator>, ActuatorConfiguration) =
                         match actuator_configs
                             .iter()
@@ -410,42 +1474,16 @@ impl Supervisor {
                                     max_angle_change: Some(1.0),
                                     max_velocity: None,
                                     command_rate_hz: Some(100.0),
+                                    ..Default::default()
                                 },
                             ),
                         };
 
                     let actuator_type = actuator.actuator_type();
-                    actuators.insert(
-                        id,
-                        ActuatorRecord {
-                            actuator,
-                            state: ActuatorState {
-                                feedback: None,
-                                last_feedback: SystemTime::now(),
-                                last_command: SystemTime::now(),
-                                ready: false,
-                                enabled: false,
-                                control_config: ControlConfig {
-                                    kp: 0.0,
-                                    kd: 0.0,
-                                    max_torque: None,
-                                    max_velocity: None,
-                                    max_current: None,
-                                },
-                                control_command: ControlCommand {
-                                    target_angle: 0.0,
-                                    target_velocity: 0.0,
-                                    kp: 0.0,
-                                    kd: 0.0,
-                                    torque: 0.0,
-                                },
-                                configuration,
-                                messages_received: 0,
-                                half_revolutions: 0,
-                                actuator_type,
-                            },
-                        },
-                    );
+                    entry.insert(ActuatorRecord {
+                        actuator,
+                        state: ActuatorState::new(actuator_type, configuration),
+                    });
                     debug!(
                         "Added actuator with ID: {} (type: {:?}) on {}",
                         id, actuator_type, transport_name
@@ -456,23 +1494,108 @@ impl Supervisor {
             time::sleep(Duration::from_millis(1)).await;
         }
 
-        let discovered_ids = self.discovered_ids.read().await;
-        Ok(discovered_ids.clone())
+        let mut discovered_ids = self.discovered_ids.read().await.clone();
+        discovered_ids.sort_unstable();
+        Ok(discovered_ids)
     }
 
+    /// Runs the control loop, sending each enabled actuator its target every
+    /// `interval`. Each iteration holds the actuators lock throughout, so the
+    /// commands sent in one iteration come from one consistent snapshot of the
+    /// targets: a concurrent `command` lands either before or after the whole
+    /// iteration, and when called faster than the loop only the latest target
+    /// is sent.
     pub async fn run(&mut self, interval: Duration) -> Result<()> {
+        if interval.is_zero() {
+            return Err(eyre::eyre!("Run loop interval must be non-zero"));
+        }
         info!("Starting supervisor");
+        self.running.store(true, Ordering::Release);
+        let _running = RunningGuard(self.running.clone());
         let mut interval = time::interval(interval);
+        {
+            let mut loop_stats = self.loop_stats.write().await;
+            *loop_stats = LoopStats {
+                started: Some(Instant::now()),
+                smoothing: loop_stats.smoothing,
+                ..Default::default()
+            };
+        }
 
         loop {
             interval.tick().await;
+            self.loop_stats.write().await.tick();
 
             {
                 let mut actuators_snapshot = self.actuators.write().await;
                 let num_actuators = actuators_snapshot.len();
+                let command_scale = self.command_scale.read().await.value();
+                // Playback and moves set targets themselves, so they count as fresh
+                let watchdog_tripped = {
+                    let mut watchdog = self.command_watchdog.write().await;
+                    if self.playback.read().await.is_some()
+                        || actuators_snapshot
+                            .values()
+                            .any(|record| record.state.slew.is_some())
+                    {
+                        watchdog.last_target = Instant::now();
+                    }
+                    watchdog.check()
+                };
+
+                // Feedforward torques in Nm from the latest positions
+                let compensation = match self.gravity_compensation.read().await.as_ref() {
+                    Some(compensate) => {
+                        let positions: HashMap<u8, f32> = actuators_snapshot
+                            .iter()
+                            .filter_map(|(&id, record)| {
+                                let feedback = record.state.feedback.clone()?;
+                                let typed = typed_feedback(record.state.actuator_type, feedback);
+                                Some((id, typed.angle_rad()))
+                            })
+                            .collect();
+                        compensate(&positions)
+                    }
+                    None => HashMap::new(),
+                };
+
+                // Advance trajectory playback
+                {
+                    let mut playback = self.playback.write().await;
+                    if let Some(active) = playback.as_ref() {
+                        let duration = active.trajectory.duration();
+                        let mut t = active.start.elapsed().as_secs_f32();
+                        let finished = !active.looping && t >= duration;
+                        if active.looping && duration > 0.0 {
+                            t %= duration;
+                        }
+
+                        for (id, position, velocity) in active.trajectory.sample(t) {
+                            if let Some(record) = actuators_snapshot.get_mut(&id) {
+                                record.state.set_target(position, velocity, 0.0);
+                            }
+                        }
+
+                        if finished {
+                            info!("Trajectory playback finished");
+                            *playback = None;
+                        }
+                    }
+                }
+
+                // Advance moves started by `move_to`
+                for record in actuators_snapshot.values_mut() {
+                    record.state.step_slew();
+                }
 
                 // Process actuators
                 for (&id, record) in actuators_snapshot.iter_mut() {
+                    if std::mem::take(&mut record.state.reapply_pending) {
+                        if let Err(e) = reapply_runtime_config(record).await {
+                            error!("Failed to reapply configuration of actuator {}: {}", id, e);
+                        }
+                    }
+
                     if record.state.enabled {
                         if record.state.ready {
                             let feedback = match record.state.feedback.as_ref() {
@@ -482,6 +1605,36 @@ impl Supervisor {
                                     continue;
                                 }
                             };
+
+                            // Commands are ignored while the actuator calibrates, so
+                            // hold off until it reports run mode again
+                            if feedback.mode == MotorMode::Calibration {
+                                trace!("Actuator {} is calibrating, skipping control", id);
+                                record.state.calibration_skips += 1;
+                                if let Err(e) = record.actuator.get_feedback().await {
+                                    error!("Failed to get feedback from actuator {}: {}", id, e);
+                                }
+                                continue;
+                            }
+
+                            // Outside MIT mode the drive follows targets written as
+                            // parameters, so only poll feedback
+                            if record
+                                .state
+                                .run_mode
+                                .is_some_and(|mode| mode != RunMode::Operation)
+                            {
+                                if let Err(e) = record.actuator.get_feedback().await {
+                                    error!("Failed to get feedback from actuator {}: {}", id, e);
+                                }
+                                continue;
+                            }
+
+                            // The ramp in `disable` sends this actuator's commands
+                            if record.state.ramping_down {
+                                continue;
+                            }
+
                             let now = SystemTime::now();
                             let mut command_valid = true;
 
@@ -497,7 +1650,9 @@ impl Supervisor {
                                             elapsed.as_secs_f32() * 1000.0,
                                             min_interval.as_secs_f32() * 1000.0
                                         );
-                                        command_valid = false;
+                                        // Leave the bus to faster actuators until
+                                        // the period has passed
+                                        continue;
                                     }
                                 }
                             }
@@ -542,19 +1697,107 @@ impl Supervisor {
                                 }
                             }
 
+                            // Check target age if configured, while the target is moving
+                            if let Some(max_age) = record.state.configuration.max_target_age {
+                                let target_time = record.state.target_time;
+                                let freeze = record.state.configuration.freeze_stale_targets;
+                                if record.state.stale_target != Some(target_time)
+                                    && record.state.control_command.target_velocity != 0.0
+                                    && target_time.elapsed() > max_age
+                                {
+                                    record.state.stale_target = Some(target_time);
+                                    if freeze {
+                                        warn!(
+                                            "Actuator {} target not updated for {:?}, holding at last feedback",
+                                            id, max_age
+                                        );
+                                        record.state.control_command.target_angle = feedback.angle;
+                                        record.state.control_command.target_velocity = 0.0;
+                                    } else {
+                                        warn!(
+                                            "Actuator {} target not updated for {:?}, skipping commands",
+                                            id, max_age
+                                        );
+                                    }
+                                }
+                                if !freeze && record.state.stale_target == Some(target_time) {
+                                    command_valid = false;
+                                }
+                            }
+
                             if cfg!(feature = "instant_command") {
                                 command_valid = false;
                             }
 
                             if command_valid {
-                                if let Err(e) = record
-                                    .actuator
-                                    .control(record.state.control_command.clone())
-                                    .await
+                                let mut cmd = record.state.outgoing_command();
+                                if let Some(horizon) =
+                                    record.state.configuration.target_extrapolation
                                 {
+                                    let limits = record.state.actuator_type.limits();
+                                    let target =
+                                        typed_command(record.state.actuator_type, cmd.clone());
+                                    let age = record
+                                        .state
+                                        .target_time
+                                        .elapsed()
+                                        .min(horizon)
+                                        .as_secs_f32();
+                                    let position = (target.target_angle_rad()
+                                        + target.target_velocity_rads() * age)
+                                        .clamp(limits.min_angle, limits.max_angle);
+                                    cmd.target_angle = normalize_value(
+                                        position,
+                                        limits.min_angle,
+                                        limits.max_angle,
+                                        -100.0,
+                                        100.0,
+                                    );
+                                }
+                                if let Some(&torque) = compensation.get(&id) {
+                                    let limits = record.state.actuator_type.limits();
+                                    let torque = torque * record.state.configuration.torque_sign;
+                                    cmd.torque = (cmd.torque
+                                        + normalize_value(
+                                            torque,
+                                            limits.min_torque,
+                                            limits.max_torque,
+                                            -100.0,
+                                            100.0,
+                                        ))
+                                    .clamp(-100.0, 100.0);
+                                }
+                                if record.state.configuration.thermal_derating {
+                                    let headroom = record
+                                        .state
+                                        .configuration
+                                        .thermal_limits
+                                        .headroom(feedback.temperature);
+                                    cmd.kp *= headroom;
+                                    cmd.kd *= headroom;
+                                    cmd.torque *= headroom;
+                                }
+                                cmd.kp *= command_scale;
+                                cmd.kd *= command_scale;
+                                cmd.torque *= command_scale;
+                                // Brake on damping alone until a new target arrives
+                                if watchdog_tripped {
+                                    cmd.kp = 0.0;
+                                    cmd.target_velocity = 0.0;
+                                    cmd.torque = 0.0;
+                                }
+                                // Extrapolation and gravity compensation can take the
+                                // target past the soft limits it was set within
+                                record.state.soft_limit_command(&mut cmd);
+
+                                if let Err(e) = record.actuator.control(cmd).await {
                                     error!("Failed to control actuator {}: {}", id, e);
+                                    record.state.command_failures += 1;
+                                    record.state.last_send_failed = true;
                                 } else {
+                                    record.state.last_send_failed = false;
                                     record.state.last_command = now;
+                                    record.state.last_command_mode = Some(RunMode::Operation);
                                 }
                             } else {
                                 if let Err(e) = record.actuator.get_feedback().await {
@@ -574,12 +1817,21 @@ impl Supervisor {
                     }
                 }
 
-                // Check timeouts and print stats with write lock
-                drop(actuators_snapshot); // Drop read lock before taking write lock
-                let mut actuators = self.actuators.write().await;
+                // Check timeouts and print stats under the same lock
+                let mut actuators = actuators_snapshot;
 
+                let disconnect_callback = self.disconnect_callback.read().await;
                 for (&id, record) in actuators.iter_mut() {
-                    if record.state.last_feedback.elapsed()? > self.feedback_timeout {
+                    let timed_out = record.state.last_feedback.elapsed()? > self.feedback_timeout;
+                    if timed_out && !record.state.disconnected {
+                        warn!("Actuator {} disconnected", id);
+                        if let Some(callback) = disconnect_callback.as_ref() {
+                            callback(id);
+                        }
+                    }
+                    record.state.disconnected = timed_out;
+
+                    if timed_out {
                         error!("Feedback timeout for actuator {}", id);
                         record.state.enabled = false;
 
@@ -589,20 +1841,57 @@ impl Supervisor {
                     }
                 }
 
-                if self.last_stats_time.elapsed()? > Duration::from_secs(5) {
+                if let Some(recording) = self.recording.write().await.as_mut() {
+                    if let Err(e) = recording.write(&actuators) {
+                        error!("Failed to write recording: {}", e);
+                    }
+                }
+
+                let stats_elapsed = self.last_stats_time.elapsed()?;
+                if self
+                    .stats_interval
+                    .is_some_and(|stats_interval| stats_elapsed > stats_interval)
+                {
                     let total_messages: u64 = actuators
                         .values()
                         .map(|record| record.state.messages_received)
                         .sum();
+                    let total_failures: u64 = actuators
+                        .values()
+                        .map(|record| record.state.command_failures)
+                        .sum();
                     info!(
-                        "Messages received: {} (avg {:.1} Hz), len={}",
+                        "Messages received: {} (avg {:.1} Hz), command failures: {}, len={}",
                         total_messages,
-                        total_messages as f32 / (5.0 * num_actuators as f32),
+                        total_messages as f32
+                            / (stats_elapsed.as_secs_f32() * num_actuators as f32),
+                        total_failures,
                         num_actuators
                     );
 
+                    let mut ids: Vec<u8> = actuators.keys().copied().collect();
+                    ids.sort_unstable();
+                    for id in ids {
+                        let record = &actuators[&id];
+                        if let Some(feedback) = record.state.feedback.clone() {
+                            let mode = feedback.mode;
+                            let temperature = feedback.temperature;
+                            let typed = typed_feedback(record.state.actuator_type, feedback);
+                            info!(
+                                "Actuator {}: angle={:.3} rad, velocity={:.3} rad/s, torque={:.3} Nm, temperature={:.1} C, mode={:?}",
+                                id,
+                                typed.angle_rad(),
+                                typed.velocity_rads(),
+                                typed.torque_nm(),
+                                temperature,
+                                mode
+                            );
+                        }
+                    }
+
                     for record in actuators.values_mut() {
                         record.state.messages_received = 0;
+                        record.state.command_failures = 0;
                     }
                     self.last_stats_time = SystemTime::now();
                 }
@@ -610,31 +1899,299 @@ impl Supervisor {
         }
     }
 
+    /// Sends the enable frame (communication type 3, `MotorIn`), switching
+    /// the drive from reset to run mode and powering its output stage. The
+    /// drive ignores MIT commands until it is enabled, so call this before
+    /// `command`; the run loop only streams to enabled actuators. Runs the
+    /// init hook afterwards if one is set; if the hook fails, the actuator is
+    /// stopped again and the hook's error returned.
     pub async fn enable(&mut self, id: u8) -> Result<()> {
+        if *self.emergency_stopped.read().await {
+            return Err(RobstrideError::EmergencyStopped.into());
+        }
         let mut actuators = self.actuators.write().await;
         let record = actuators
             .get_mut(&id)
-            .ok_or_else(|| eyre::eyre!("Actuator not found"))?;
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
         record.actuator.enable().await?;
         record.state.enabled = true;
+        record.state.enable_requested = SystemTime::now();
+        *self.shut_down.write().await = false;
+
+        self.run_init_hook(id, record).await
+    }
+
+    // Runs the init hook on a freshly enabled actuator. If it fails, the
+    // actuator is stopped again rather than left running without whatever
+    // the hook was meant to set up.
+    async fn run_init_hook(&self, id: u8, record: &mut ActuatorRecord) -> Result<()> {
+        let Some(hook) = &self.init_hook else {
+            return Ok(());
+        };
+        if let Err(e) = hook.run(record.actuator.as_ref()).await {
+            record.state.enabled = false;
+            if let Err(disable_error) = record.actuator.disable(false).await {
+                error!(
+                    "Failed to stop actuator {} after its init hook failed: {}",
+                    id, disable_error
+                );
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Enables each actuator in `ids`, retrying up to `attempts` times until
+    /// its feedback reports run mode. Runs the init hook once an actuator is
+    /// up, and returns the IDs that never came up or whose init hook failed;
+    /// those are stopped again.
+    pub async fn enable_all(&mut self, ids: &[u8], attempts: u32) -> Result<Vec<u8>> {
+        if *self.emergency_stopped.read().await {
+            return Err(RobstrideError::EmergencyStopped.into());
+        }
+        let mut failed = Vec::new();
+
+        for &id in ids {
+            let mut running = false;
+            for attempt in 1..=attempts {
+                let requested = SystemTime::now();
+                {
+                    let actuators = self.actuators.read().await;
+                    let record = actuators
+                        .get(&id)
+                        .ok_or(RobstrideError::ActuatorNotFound(id))?;
+                    if let Err(e) = record.actuator.enable().await {
+                        warn!(
+                            "Failed to enable actuator {} (attempt {}): {}",
+                            id, attempt, e
+                        );
+                        continue;
+                    }
+                }
+
+                let enable_end = requested + ENABLE_TIMEOUT;
+                while !running && SystemTime::now() < enable_end {
+                    {
+                        let actuators = self.actuators.read().await;
+                        running = actuators.get(&id).is_some_and(|record| {
+                            record.state.last_feedback >= requested
+                                && record
+                                    .state
+                                    .feedback
+                                    .as_ref()
+                                    .is_some_and(|feedback| feedback.mode == MotorMode::Run)
+                        });
+                    }
+                    time::sleep(Duration::from_millis(1)).await;
+                }
+                if running {
+                    break;
+                }
+                debug!("Actuator {} not running after attempt {}", id, attempt);
+            }
+
+            if !running {
+                warn!("Actuator {} did not enable after {} attempts", id, attempts);
+                failed.push(id);
+                continue;
+            }
+
+            let mut actuators = self.actuators.write().await;
+            if let Some(record) = actuators.get_mut(&id) {
+                record.state.enabled = true;
+                record.state.enable_requested = SystemTime::now();
+                *self.shut_down.write().await = false;
+                if let Err(e) = self.run_init_hook(id, record).await {
+                    error!("Init hook failed for actuator {}: {}", id, e);
+                    failed.push(id);
+                }
+            }
+        }
+
+        Ok(failed)
+    }
+
+    /// Sets the hook `enable` runs after enabling each actuator, replacing any
+    /// previous one.
+    pub fn set_init_hook(&mut self, hook: Option<Arc<dyn InitHook>>) {
+        self.init_hook = hook;
+    }
+
+    /// Sets the hook `shutdown` runs before disabling the actuators, replacing
+    /// any previous one.
+    pub fn set_shutdown_hook(&mut self, hook: Option<Arc<dyn ShutdownHook>>) {
+        self.shutdown_hook = hook;
+    }
+
+    /// Stops trajectory playback, runs the shutdown hook and then disables
+    /// every enabled actuator, ramping down as set by `set_shutdown_ramp`,
+    /// and finally stops any recording.
+    /// Failures are logged and do not stop the remaining actuators from
+    /// being disabled.
+    ///
+    /// Shutdown happens once: later calls, e.g. from both a Ctrl-C handler
+    /// and the end of `main`, on this instance or any controller clone, return
+    /// immediately until an actuator is enabled again.
+    pub async fn shutdown(&mut self) -> Result<()> {
+        {
+            let mut shut_down = self.shut_down.write().await;
+            if *shut_down {
+                debug!("Already shut down");
+                return Ok(());
+            }
+            *shut_down = true;
+        }
+
+        info!("Shutting down actuators");
+        self.stop_playback().await;
+
+        if let Some(hook) = self.shutdown_hook.clone() {
+            if let Err(e) = hook.run(self).await {
+                error!("Shutdown hook failed: {}", e);
+            }
+        }
+
+        let enabled: Vec<u8> = self
+            .actuators
+            .read()
+            .await
+            .iter()
+            .filter(|(_, record)| record.state.enabled)
+            .map(|(&id, _)| id)
+            .collect();
+        for id in enabled {
+            if let Err(e) = self.disable(id, false).await {
+                error!("Failed to disable actuator {}: {}", id, e);
+            }
+        }
+
+        if let Err(e) = self.stop_recording().await {
+            error!("Failed to finish recording: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Stops every actuator at once, without the shutdown hook or ramp: stops
+    /// playback and moves, zeroes kp, kd and torque of each target, sends
+    /// each actuator a zero-stiffness, zero-torque command and then the stop
+    /// frame. Until `clear_emergency_stop`, actuators can't be enabled again,
+    /// so the run loop sends no further commands, and `command`,
+    /// `command_all`, `move_to`, `move_relative`, `control`,
+    /// `position_control`, `speed_control`, `current_control`, `with_gains`,
+    /// `restore_targets` and `replay_recording` fail with
+    /// `RobstrideError::EmergencyStopped` without changing any target, gain
+    /// or run mode. Send failures are logged and do not stop the remaining
+    /// actuators.
+    pub async fn emergency_stop(&self) -> Result<()> {
+        *self.emergency_stopped.write().await = true;
+        *self.playback.write().await = None;
+        warn!("Emergency stop");
+
+        let mut actuators = self.actuators.write().await;
+        for (&id, record) in actuators.iter_mut() {
+            record.state.slew = None;
+            record.state.control_command.kp = 0.0;
+            record.state.control_command.kd = 0.0;
+            record.state.control_command.torque = 0.0;
+            record.state.enabled = false;
+
+            let mut cmd = record.state.control_command.clone();
+            cmd.target_velocity = 0.0;
+            if let Err(e) = record.actuator.control(cmd).await {
+                error!("Failed to send zero command to actuator {}: {}", id, e);
+            }
+            if let Err(e) = record.actuator.disable(false).await {
+                error!("Failed to stop actuator {}: {}", id, e);
+            }
+        }
         Ok(())
     }
 
+    /// Releases the emergency stop latch. Actuators stay disabled until
+    /// enabled again, and their gains stay zero until set.
+    pub async fn clear_emergency_stop(&self) {
+        *self.emergency_stopped.write().await = false;
+    }
+
+    pub async fn is_emergency_stopped(&self) -> bool {
+        *self.emergency_stopped.read().await
+    }
+
+    /// Sets how long `disable` ramps stiffness and torque down before stopping
+    /// an enabled actuator, so a loaded joint lowers instead of dropping. Zero
+    /// (the default) stops immediately.
+    ///
+    /// Only `disable` and `shutdown` ramp. `emergency_stop` and the run loop's
+    /// feedback timeout stop at once, and nothing is sent when the run loop
+    /// exits, so call `shutdown` afterwards to lower loaded joints.
+    pub fn set_shutdown_ramp(&mut self, ramp: Duration) {
+        self.shutdown_ramp = ramp;
+    }
+
+    /// Sends the stop frame (communication type 4, `MotorReset`), returning
+    /// the drive to reset mode with its output stage off, after the shutdown
+    /// ramp if one is set. With `clear_fault` the drive also clears latched
+    /// faults. The run loop keeps polling feedback but sends no more commands
+    /// until the actuator is enabled again.
     pub async fn disable(&mut self, id: u8, clear_fault: bool) -> Result<()> {
+        let enabled = {
+            let actuators = self.actuators.read().await;
+            let record = actuators
+                .get(&id)
+                .ok_or(RobstrideError::ActuatorNotFound(id))?;
+            record.state.enabled
+        };
+        if enabled && !self.shutdown_ramp.is_zero() {
+            self.ramp_down(id).await;
+        }
+
         let mut actuators = self.actuators.write().await;
         let record = actuators
             .get_mut(&id)
-            .ok_or_else(|| eyre::eyre!("Actuator not found"))?;
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        record.state.ramping_down = false;
         record.actuator.disable(clear_fault).await?;
         record.state.enabled = false;
         Ok(())
     }
 
+    // Scales kp, kd and torque of the actuator's command down to zero over the
+    // shutdown ramp. The actuators lock is only held for each step, so the
+    // run loop, feedback and `emergency_stop` carry on in between; the ramp
+    // ends early if the actuator is disabled meanwhile.
+    async fn ramp_down(&self, id: u8) {
+        let step = SHUTDOWN_RAMP_STEP.min(self.shutdown_ramp);
+        let steps = (self.shutdown_ramp.as_secs_f32() / step.as_secs_f32()).ceil() as u32;
+        for i in 1..=steps {
+            {
+                let mut actuators = self.actuators.write().await;
+                let Some(record) = actuators.get_mut(&id) else {
+                    return;
+                };
+                if !record.state.enabled {
+                    return;
+                }
+                record.state.ramping_down = true;
+
+                let scale = 1.0 - i as f32 / steps as f32;
+                let mut cmd = record.state.outgoing_command();
+                cmd.kp *= scale;
+                cmd.kd *= scale;
+                cmd.torque *= scale;
+                if let Err(e) = record.actuator.control(cmd).await {
+                    error!("Failed to ramp down actuator {}: {}", id, e);
+                    return;
+                }
+            }
+            time::sleep(step).await;
+        }
+    }
+
     pub async fn configure(&mut self, id: u8, config: ControlConfig) -> Result<()> {
         let mut actuators = self.actuators.write().await;
         let record = actuators
             .get_mut(&id)
-            .ok_or_else(|| eyre::eyre!("Actuator not found"))?;
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
 
         let cmd = match record.state.actuator_type {
             ActuatorType::RobStride00 => RobStride00Command {
@@ -687,77 +2244,574 @@ impl Supervisor {
         Ok(())
     }
 
+    /// Writes the runtime configuration the supervisor issued to the actuator
+    /// again: the limits from `configure`, the run mode from `set_run_mode`
+    /// and the gains and targets last written by `position_control`,
+    /// `speed_control` and `current_control`. These are not saved to flash,
+    /// so a power-cycled drive forgets them. The run loop does this on its
+    /// next cycle when an enabled actuator unexpectedly reports reset mode or
+    /// its feedback resumes after a timeout; call it after re-adding a
+    /// transport or other manual recovery.
+    pub async fn reapply_config(&self, id: u8) -> Result<()> {
+        let actuators = self.actuators.read().await;
+        let record = actuators
+            .get(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        reapply_runtime_config(record).await
+    }
+
+    /// Runs `f` with the actuator's gains temporarily set to `kp` and `kd`,
+    /// e.g. for a stiff positioning move, then restores the previous gains,
+    /// also if `f` panics. `f` gets a controller clone to command with. If an
+    /// emergency stop happens during `f`, the gains are left at zero.
+    pub async fn with_gains<F, Fut, T>(&mut self, id: u8, kp: f32, kd: f32, f: F) -> Result<T>
+    where
+        F: FnOnce(Supervisor) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let (previous_kp, previous_kd) = self.set_gains(id, kp, kd).await?;
+        let result = AssertUnwindSafe(f(self.clone_controller()))
+            .catch_unwind()
+            .await;
+        if let Err(e) = self.set_gains(id, previous_kp, previous_kd).await {
+            if !matches!(
+                e.downcast_ref::<RobstrideError>(),
+                Some(RobstrideError::EmergencyStopped)
+            ) {
+                return Err(e);
+            }
+        }
+
+        match result {
+            Ok(value) => Ok(value),
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+
+    // Sets kp and kd without writing any limits, returning the previous gains.
+    // Refused while the emergency stop is latched.
+    async fn set_gains(&self, id: u8, kp: f32, kd: f32) -> Result<(f32, f32)> {
+        let mut actuators = self.actuators.write().await;
+        if *self.emergency_stopped.read().await {
+            return Err(RobstrideError::EmergencyStopped.into());
+        }
+        let record = actuators
+            .get_mut(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+
+        let previous = (
+            record.state.control_config.kp,
+            record.state.control_config.kd,
+        );
+        record.state.set_gains(kp, kd);
+        Ok(previous)
+    }
+
+    /// Sets the target of an actuator, sent by the run loop. Fails if the
+    /// actuator reports reset mode since it was last enabled, where it would
+    /// ignore the command, unless `auto_start` is configured. Returns the
+    /// (position, velocity, torque) target set, clamped to the soft limits
+    /// and the model's range.
     pub async fn command(
         &mut self,
         id: u8,
         position: f32,
         velocity: f32,
         torque: f32,
-    ) -> Result<()> {
+    ) -> Result<(f32, f32, f32)> {
         let mut actuators = self.actuators.write().await;
         let record = actuators
             .get_mut(&id)
-            .ok_or_else(|| eyre::eyre!("Actuator not found"))?;
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
 
-        let position = denormalize_radians(position, record.state.half_revolutions);
+        ensure_started(id, record, *self.emergency_stopped.read().await).await?;
+        let applied = record.state.set_target(position, velocity, torque);
+        self.command_watchdog.write().await.last_target = Instant::now();
 
-        let cmd = match record.state.actuator_type {
-            ActuatorType::RobStride00 => RobStride00Command {
-                target_angle_rad: position,
-                target_velocity_rads: velocity,
-                torque_nm: torque,
-                ..Default::default()
+        if cfg!(feature = "instant_command") {
+            record
+                .actuator
+                .control(record.state.outgoing_command())
+                .await?;
+            record.state.last_command_mode = Some(RunMode::Operation);
+        }
+
+        Ok(applied)
+    }
+
+    /// Sets the targets of several actuators at once, from ID to (position,
+    /// velocity, torque) in the units of `command`. All targets are written
+    /// under one lock, so the run loop sees either none or all of them.
+    /// Every ID and actuator is checked before anything is sent, so nothing
+    /// is enabled or changed if any ID is unknown or any actuator is not
+    /// started and can't be auto-started. Returns the targets set, clamped
+    /// like those of `command`.
+    pub async fn command_all(
+        &mut self,
+        targets: &HashMap<u8, (f32, f32, f32)>,
+    ) -> Result<HashMap<u8, (f32, f32, f32)>> {
+        let emergency_stopped = *self.emergency_stopped.read().await;
+        let mut actuators = self.actuators.write().await;
+        let mut to_start = Vec::new();
+        for &id in targets.keys() {
+            let record = actuators
+                .get(&id)
+                .ok_or(RobstrideError::ActuatorNotFound(id))?;
+            if needs_start(id, record, emergency_stopped)? {
+                to_start.push(id);
             }
-            .to_control_command(),
-            ActuatorType::RobStride01 => RobStride01Command {
-                target_angle_rad: position,
-                target_velocity_rads: velocity,
-                torque_nm: torque,
-                ..Default::default()
+        }
+        for id in to_start {
+            if let Some(record) = actuators.get_mut(&id) {
+                auto_start(id, record).await?;
             }
-            .to_control_command(),
-            ActuatorType::RobStride02 => RobStride02Command {
-                target_angle_rad: position,
-                target_velocity_rads: velocity,
-                torque_nm: torque,
-                ..Default::default()
+        }
+
+        let mut applied = HashMap::new();
+        for (&id, &(position, velocity, torque)) in targets {
+            if let Some(record) = actuators.get_mut(&id) {
+                applied.insert(id, record.state.set_target(position, velocity, torque));
             }
-            .to_control_command(),
-            ActuatorType::RobStride03 => RobStride03Command {
-                target_angle_rad: position,
-                target_velocity_rads: velocity,
-                torque_nm: torque,
-                ..Default::default()
+        }
+        self.command_watchdog.write().await.last_target = Instant::now();
+
+        if cfg!(feature = "instant_command") {
+            for id in targets.keys() {
+                if let Some(record) = actuators.get_mut(id) {
+                    record
+                        .actuator
+                        .control(record.state.outgoing_command())
+                        .await?;
+                    record.state.last_command_mode = Some(RunMode::Operation);
+                }
             }
-            .to_control_command(),
-            ActuatorType::RobStride04 => RobStride04Command {
-                target_angle_rad: position,
-                target_velocity_rads: velocity,
-                torque_nm: torque,
-                ..Default::default()
+        }
+
+        Ok(applied)
+    }
+
+    /// Returns the targets and gains of all actuators, read under a single
+    /// lock, for `restore_targets` to put back after a temporary change of
+    /// behavior. Values are in the drive's normalized command units.
+    pub async fn snapshot_targets(&self) -> HashMap<u8, ControlCommand> {
+        let actuators = self.actuators.read().await;
+        actuators
+            .iter()
+            .map(|(&id, record)| (id, record.state.control_command.clone()))
+            .collect()
+    }
+
+    /// Puts back targets and gains taken by `snapshot_targets`, under a
+    /// single lock so the run loop never sends a mix of old and new targets.
+    /// Targets are clamped to the current soft limits. Cancels `move_to`
+    /// moves of the restored actuators, and stops playback if it drives any
+    /// of them. Nothing is changed if any ID is unknown. Actuators missing
+    /// from the snapshot keep their current targets. Refused while the
+    /// emergency stop is latched.
+    pub async fn restore_targets(&mut self, snapshot: &HashMap<u8, ControlCommand>) -> Result<()> {
+        let mut actuators = self.actuators.write().await;
+        if *self.emergency_stopped.read().await {
+            return Err(RobstrideError::EmergencyStopped.into());
+        }
+        if let Some(id) = snapshot.keys().find(|id| !actuators.contains_key(id)) {
+            return Err(RobstrideError::ActuatorNotFound(*id).into());
+        }
+
+        // Playback would overwrite the restored targets on the next cycle
+        let mut playback = self.playback.write().await;
+        if playback.as_ref().is_some_and(|active| {
+            active
+                .trajectory
+                .ids()
+                .iter()
+                .any(|id| snapshot.contains_key(id))
+        }) {
+            *playback = None;
+        }
+
+        let now = Instant::now();
+        for (id, command) in snapshot {
+            if let Some(record) = actuators.get_mut(id) {
+                record.state.slew = None;
+                let gains = typed_command(record.state.actuator_type, command.clone());
+                record.state.control_config.kp = gains.kp();
+                record.state.control_config.kd = gains.kd();
+                let mut command = command.clone();
+                record.state.soft_limit_command(&mut command);
+                record.state.control_command = command;
+                record.state.target_time = now;
             }
-            .to_control_command(),
-        };
+        }
+        self.command_watchdog.write().await.last_target = Instant::now();
+        Ok(())
+    }
+
+    /// Moves an actuator to `position` (radians, like `command`) without
+    /// jumping: the target starts at the current position and the run loop
+    /// steps it toward `position` at `max_velocity` (rad/s) each cycle.
+    /// Replaces any move in progress on this actuator; a later `command`,
+    /// `command_all`, `move_relative` or `restore_targets` cancels it. See `is_move_complete`.
+    /// Returns the position moved to, clamped like that of `command`.
+    pub async fn move_to(&mut self, id: u8, position: f32, max_velocity: f32) -> Result<f32> {
+        if !(max_velocity.is_finite() && max_velocity > 0.0) {
+            return Err(eyre::eyre!("Maximum velocity must be positive"));
+        }
+
+        let mut actuators = self.actuators.write().await;
+        let record = actuators
+            .get_mut(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        ensure_started(id, record, *self.emergency_stopped.read().await).await?;
+        let feedback_angle = record
+            .state
+            .feedback
+            .as_ref()
+            .map(|feedback| feedback.angle)
+            .ok_or_else(|| eyre::eyre!("No feedback from actuator {}", id))?;
+
+        let limits = record.state.actuator_type.limits();
+        let (applied, target) = record.state.limit_position(position);
+
+        // Feedback and targets share the drive's normalized angle scale
+        record.state.control_command.target_angle = feedback_angle;
+        record.state.control_command.target_velocity = 0.0;
+        record.state.slew = Some(Slew {
+            target,
+            max_velocity: max_velocity.min(limits.max_velocity),
+            last_step: Instant::now(),
+        });
+        self.command_watchdog.write().await.last_target = Instant::now();
+        Ok(applied)
+    }
+
+    /// Returns whether the last `move_to` of an actuator has reached its
+    /// target position, or was cancelled. True if there was none.
+    pub async fn is_move_complete(&self, id: u8) -> Result<bool> {
+        let actuators = self.actuators.read().await;
+        let record = actuators
+            .get(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        Ok(record.state.slew.is_none())
+    }
+
+    /// Limits the target position of an actuator to `min..=max` radians.
+    /// Targets set afterwards are clamped to it, and so is every command the
+    /// run loop sends from its next cycle, including the current target.
+    pub async fn set_position_limits(&mut self, id: u8, min: f32, max: f32) -> Result<()> {
+        if !(min.is_finite() && max.is_finite() && min <= max) {
+            return Err(eyre::eyre!(
+                "Position limits must be finite with min <= max, got {}..{}",
+                min,
+                max
+            ));
+        }
+        self.update_soft_limits(id, |limits| limits.position = Some((min, max)))
+            .await
+    }
+
+    /// Limits the magnitude of the target velocity of an actuator, in rad/s.
+    pub async fn set_velocity_limit(&mut self, id: u8, max: f32) -> Result<()> {
+        if !(max.is_finite() && max >= 0.0) {
+            return Err(eyre::eyre!("Velocity limit must be non-negative"));
+        }
+        self.update_soft_limits(id, |limits| limits.max_velocity = Some(max))
+            .await
+    }
+
+    /// Limits the magnitude of the target torque of an actuator, in Nm.
+    pub async fn set_torque_limit(&mut self, id: u8, max: f32) -> Result<()> {
+        if !(max.is_finite() && max >= 0.0) {
+            return Err(eyre::eyre!("Torque limit must be non-negative"));
+        }
+        self.update_soft_limits(id, |limits| limits.max_torque = Some(max))
+            .await
+    }
+
+    /// Returns the soft limits of an actuator.
+    pub async fn soft_limits(&self, id: u8) -> Result<SoftLimits> {
+        let actuators = self.actuators.read().await;
+        let record = actuators
+            .get(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        Ok(record.state.soft_limits)
+    }
+
+    /// Replaces all soft limits of an actuator, e.g. with
+    /// `SoftLimits::default()` to remove them.
+    pub async fn set_soft_limits(&mut self, id: u8, soft_limits: SoftLimits) -> Result<()> {
+        self.update_soft_limits(id, |limits| *limits = soft_limits)
+            .await
+    }
+
+    async fn update_soft_limits(&self, id: u8, f: impl FnOnce(&mut SoftLimits)) -> Result<()> {
+        let mut actuators = self.actuators.write().await;
+        let record = actuators
+            .get_mut(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        f(&mut record.state.soft_limits);
+        Ok(())
+    }
+
+    /// Moves the target position by `delta` radians, clamped to the soft
+    /// limits and the model's angle range. Based on the current target rather
+    /// than the feedback, so repeated calls while the joint is still moving
+    /// don't accumulate drift. Returns the new target position, normalized to
+    /// one turn like the positions of `command`.
+    pub async fn move_relative(&mut self, id: u8, delta: f32) -> Result<f32> {
+        let mut actuators = self.actuators.write().await;
+        let record = actuators
+            .get_mut(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
 
-        record.state.control_command.target_angle = cmd.target_angle;
-        record.state.control_command.target_velocity = cmd.target_velocity;
-        record.state.control_command.torque = cmd.torque;
+        ensure_started(id, record, *self.emergency_stopped.read().await).await?;
+        let limits = record.state.actuator_type.limits();
+        let target = typed_command(
+            record.state.actuator_type,
+            record.state.control_command.clone(),
+        )
+        .target_angle_rad();
+        record.state.slew = None;
+        let position = record
+            .state
+            .soft_limit_position((target + delta).clamp(limits.min_angle, limits.max_angle));
+        record.state.control_command.target_angle =
+            normalize_value(position, limits.min_angle, limits.max_angle, -100.0, 100.0);
+        record.state.target_time = Instant::now();
+        self.command_watchdog.write().await.last_target = Instant::now();
 
         if cfg!(feature = "instant_command") {
             record
                 .actuator
-                .control(record.state.control_command.clone())
+                .control(record.state.outgoing_command())
                 .await?;
+            record.state.last_command_mode = Some(RunMode::Operation);
+        }
+
+        Ok(normalize_radians(position).0)
+    }
+
+    /// Same as `command` with the position in degrees and the velocity in
+    /// degrees per second, also for the target returned.
+    pub async fn command_deg(
+        &mut self,
+        id: u8,
+        position_deg: f32,
+        velocity_degs: f32,
+        torque: f32,
+    ) -> Result<(f32, f32, f32)> {
+        let (position, velocity, torque) = self
+            .command(
+                id,
+                position_deg.to_radians(),
+                velocity_degs.to_radians(),
+                torque,
+            )
+            .await?;
+        Ok((position.to_degrees(), velocity.to_degrees(), torque))
+    }
+
+    /// Commands a position in degrees with no velocity or torque feedforward.
+    /// Returns the position set, clamped like that of `command`.
+    pub async fn set_position_deg(&mut self, id: u8, position_deg: f32) -> Result<f32> {
+        Ok(self.command_deg(id, position_deg, 0.0, 0.0).await?.0)
+    }
+
+    /// Plays back a trajectory CSV (see `Trajectory`) from the run loop,
+    /// replacing any playback in progress. Targets are interpolated between
+    /// rows each cycle.
+    pub async fn play_trajectory(&self, path: impl AsRef<Path>, looping: bool) -> Result<()> {
+        self.start_playback(Trajectory::from_csv(path)?, looping)
+            .await
+    }
+
+    /// Moves the actuators in `targets` (ID to position, in radians like
+    /// `command`) so that all of them start together and arrive after
+    /// `duration`, each interpolating linearly from its current position.
+    /// Runs as a playback, replacing any in progress.
+    pub async fn move_to_synchronized(
+        &self,
+        targets: &HashMap<u8, f32>,
+        duration: Duration,
+    ) -> Result<()> {
+        if duration.is_zero() {
+            return Err(eyre::eyre!("Move duration must be non-zero"));
+        }
+
+        let mut ids: Vec<u8> = targets.keys().copied().collect();
+        ids.sort_unstable();
+        let mut start = Vec::with_capacity(ids.len());
+        for &id in &ids {
+            let (feedback, _) = self
+                .get_feedback(id)
+                .await?
+                .ok_or_else(|| eyre::eyre!("No feedback from actuator {}", id))?;
+            start.push(feedback.angle);
+        }
+        let end = ids.iter().map(|id| targets[id]).collect();
+
+        let trajectory = Trajectory::new(ids, vec![0.0, duration.as_secs_f32()], vec![start, end])?;
+        self.start_playback(trajectory, false).await
+    }
+
+    /// Moves an actuator to `position` (radians) with a trapezoidal velocity
+    /// profile limited to `max_velocity` (rad/s) and `max_acceleration`
+    /// (rad/s^2), starting from its current position. The drive has no
+    /// documented acceleration parameter, so the run loop streams the profile
+    /// as a playback, replacing any in progress; `is_playing` turns false on
+    /// arrival.
+    pub async fn profiled_move(
+        &self,
+        id: u8,
+        position: f32,
+        max_velocity: f32,
+        max_acceleration: f32,
+    ) -> Result<()> {
+        let (feedback, _) = self
+            .get_feedback(id)
+            .await?
+            .ok_or_else(|| eyre::eyre!("No feedback from actuator {}", id))?;
+        let trajectory =
+            Trajectory::trapezoidal(id, feedback.angle, position, max_velocity, max_acceleration)?;
+        self.start_playback(trajectory, false).await
+    }
+
+    async fn start_playback(&self, trajectory: Trajectory, looping: bool) -> Result<()> {
+        {
+            let actuators = self.actuators.read().await;
+            if let Some(id) = trajectory
+                .ids()
+                .iter()
+                .find(|id| !actuators.contains_key(id))
+            {
+                return Err(RobstrideError::ActuatorNotFound(*id).into());
+            }
+        }
+
+        let mut playback = self.playback.write().await;
+        *playback = Some(Playback {
+            trajectory,
+            start: Instant::now(),
+            looping,
+        });
+        Ok(())
+    }
+
+    /// Starts recording every target sent and every feedback frame received
+    /// to a CSV file at `path` (see `Recorder`), written by the run loop each
+    /// cycle. Replaces any recording in progress.
+    pub async fn start_recording(&self, path: impl AsRef<Path>) -> Result<()> {
+        let recorder = Recorder::create(path)?;
+        let previous = self.recording.write().await.replace(ActiveRecording {
+            recorder,
+            last_feedback: HashMap::new(),
+        });
+        if let Some(mut previous) = previous {
+            previous.recorder.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Stops recording and flushes the file.
+    pub async fn stop_recording(&self) -> Result<()> {
+        if let Some(mut recording) = self.recording.write().await.take() {
+            recording.recorder.flush()?;
         }
+        Ok(())
+    }
+
+    /// Replays the targets and gains of a recording made with
+    /// `start_recording`, at their original timing. Rows recorded in the same
+    /// cycle are applied under one lock. Returns once the last row is applied;
+    /// the run loop must be running to send them. Fails with
+    /// `RobstrideError::EmergencyStopped`, applying no further rows, once the
+    /// emergency stop is latched.
+    pub async fn replay_recording(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let recording = Recording::from_csv(path)?;
+        let targets: Vec<&RecordedSample> = recording.targets().collect();
+        let start = time::Instant::now();
+
+        for cycle in targets.chunk_by(|a, b| a.time == b.time) {
+            let at = Duration::from_secs_f32(cycle[0].time.max(0.0));
+            time::sleep_until(start + at).await;
 
+            let mut actuators = self.actuators.write().await;
+            if *self.emergency_stopped.read().await {
+                return Err(RobstrideError::EmergencyStopped.into());
+            }
+            if let Some(sample) = cycle.iter().find(|s| !actuators.contains_key(&s.id)) {
+                return Err(RobstrideError::ActuatorNotFound(sample.id).into());
+            }
+            for sample in cycle {
+                if let Some(record) = actuators.get_mut(&sample.id) {
+                    record
+                        .state
+                        .set_target(sample.position, sample.velocity, sample.torque);
+                    record.state.set_gains(sample.kp, sample.kd);
+                }
+            }
+            self.command_watchdog.write().await.last_target = Instant::now();
+        }
         Ok(())
     }
 
+    pub async fn is_playing(&self) -> bool {
+        self.playback.read().await.is_some()
+    }
+
+    /// Stops trajectory playback, holding the last interpolated targets.
+    pub async fn stop_playback(&self) {
+        *self.playback.write().await = None;
+    }
+
+    /// Sets the run mode of each actuator and reads it back to confirm the
+    /// switch. Returns the IDs of actuators that did not switch; only the
+    /// confirmed actuators have their recorded run mode updated.
+    pub async fn set_run_mode(&mut self, ids: &[u8], mode: RunMode) -> Result<Vec<u8>> {
+        let run_mode_index = RobStride04Parameter::RunMode.metadata().index;
+        let mut failed = Vec::new();
+
+        for &id in ids {
+            {
+                let actuators = self.actuators.read().await;
+                let record = actuators
+                    .get(&id)
+                    .ok_or(RobstrideError::ActuatorNotFound(id))?;
+                if let Err(e) = record.actuator.set_run_mode(mode).await {
+                    error!("Failed to set run mode of actuator {}: {}", id, e);
+                    failed.push(id);
+                    continue;
+                }
+            }
+
+            let confirmed = match self.read_parameter(id, run_mode_index).await {
+                Ok(data) => RunMode::from_u32(data & 0xFF),
+                Err(e) => {
+                    error!("Failed to read back run mode of actuator {}: {}", id, e);
+                    None
+                }
+            };
+
+            let mut actuators = self.actuators.write().await;
+            if let Some(record) = actuators.get_mut(&id) {
+                record.state.run_mode = confirmed;
+            }
+            if confirmed != Some(mode) {
+                warn!(
+                    "Actuator {} did not switch to {:?}, reports {:?}",
+                    id, mode, confirmed
+                );
+                failed.push(id);
+            }
+        }
+
+        Ok(failed)
+    }
+
     pub async fn set_id(&mut self, id: u8, new_id: u8) -> Result<()> {
         let mut actuators = self.actuators.write().await;
         let record = actuators
             .get_mut(&id)
-            .ok_or_else(|| eyre::eyre!("Actuator not found"))?;
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
         record.actuator.set_id(new_id).await?;
 
         let record = actuators.remove(&id).unwrap();
@@ -769,87 +2823,1435 @@ impl Supervisor {
         let actuators = self.actuators.read().await;
         let record = actuators
             .get(&id)
-            .ok_or_else(|| eyre::eyre!("Actuator not found"))?;
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
         record.actuator.get_uuid().await
     }
 
     pub async fn control(&mut self, id: u8, cmd: ControlCommand) -> Result<()> {
+        if *self.emergency_stopped.read().await {
+            return Err(RobstrideError::EmergencyStopped.into());
+        }
+        let mut actuators = self.actuators.write().await;
+        let record = actuators
+            .get_mut(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        record.actuator.control(cmd).await?;
+        record.state.last_command_mode = Some(RunMode::Operation);
+        Ok(())
+    }
+
+    /// Returns the mode of the last command sent to the actuator, or `None` if
+    /// none has been sent. Control frames are MIT commands, so this is
+    /// `RunMode::Operation` once the actuator has been commanded.
+    pub async fn current_command_mode(&self, id: u8) -> Result<Option<RunMode>> {
         let actuators = self.actuators.read().await;
         let record = actuators
             .get(&id)
-            .ok_or_else(|| eyre::eyre!("Actuator not found"))?;
-        record.actuator.control(cmd).await
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        Ok(record.state.last_command_mode)
     }
 
+    /// Returns the latest feedback of an actuator in radians, rad/s and Nm,
+    /// with the angle normalized to one turn, and when it was received.
+    /// `Ok(None)` means the actuator hasn't reported yet; an ID the supervisor
+    /// doesn't manage is an `ActuatorNotFound` error.
     pub async fn get_feedback(&self, id: u8) -> Result<Option<(FeedbackFrame, SystemTime)>> {
         let actuators = self.actuators.read().await;
-        let record = actuators.get(&id);
-        if let Some(record) = record {
-            if let Some(mut feedback) = record.state.feedback.clone() {
-                let typed_feedback: Box<dyn TypedFeedbackData> = match record.state.actuator_type {
-                    ActuatorType::RobStride00 => {
-                        Box::new(RobStride00Feedback::from_feedback_frame(feedback.clone()))
-                    }
-                    ActuatorType::RobStride01 => {
-                        Box::new(RobStride01Feedback::from_feedback_frame(feedback.clone()))
-                    }
-                    ActuatorType::RobStride02 => {
-                        Box::new(RobStride02Feedback::from_feedback_frame(feedback.clone()))
-                    }
-                    ActuatorType::RobStride03 => {
-                        Box::new(RobStride03Feedback::from_feedback_frame(feedback.clone()))
+        let record = actuators
+            .get(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        if let Some(feedback) = record.state.feedback.clone() {
+            let mut feedback = feedback_in_units(record.state.actuator_type, feedback);
+
+            // Log feedback information
+            debug!("Motor {} feedback:", id);
+            debug!("  Angle: {:?}", feedback.angle);
+            debug!("  Velocity: {:?}", feedback.velocity);
+            debug!("  Torque: {:?}", feedback.torque);
+            debug!("  Temperature: {:?}", feedback.temperature);
+            debug!("  Faults:");
+            debug!("    Uncalibrated: {:?}", feedback.fault_uncalibrated);
+            debug!("    Hall encoding: {:?}", feedback.fault_hall_encoding);
+            debug!(
+                "    Magnetic encoding: {:?}",
+                feedback.fault_magnetic_encoding
+            );
+            debug!(
+                "    Over temperature: {:?}",
+                feedback.fault_over_temperature
+            );
+            debug!("    Overcurrent: {:?}", feedback.fault_overcurrent);
+            debug!("    Undervoltage: {:?}", feedback.fault_undervoltage);
+
+            feedback.angle = normalize_radians(feedback.angle).0;
+            return Ok(Some((feedback, record.state.last_feedback)));
+        }
+        Ok(None)
+    }
+
+    /// Returns whether commands can be streamed: the run loop is running,
+    /// every transport is connected, and every actuator reports feedback
+    /// without faults and isn't calibrating.
+    pub async fn is_ready(&self) -> bool {
+        self.readiness_report().await.is_ready()
+    }
+
+    /// Details the conditions checked by `is_ready`.
+    pub async fn readiness_report(&self) -> ReadinessReport {
+        let mut report = ReadinessReport {
+            running: self.running.load(Ordering::Acquire),
+            ..Default::default()
+        };
+
+        let disconnected: Vec<(String, Arc<RwLock<bool>>)> = {
+            let transports = self.transports.read().await;
+            transports
+                .iter()
+                .map(|(name, transport)| (name.clone(), transport.disconnected.clone()))
+                .collect()
+        };
+        for (name, disconnected) in disconnected {
+            if *disconnected.read().await {
+                report.disconnected_transports.push(name);
+            }
+        }
+        report.disconnected_transports.sort();
+
+        let actuators = self.actuators.read().await;
+        let mut ids: Vec<u8> = actuators.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            let state = &actuators[&id].state;
+            match &state.feedback {
+                Some(feedback) if state.ready => {
+                    if feedback.mode == MotorMode::Calibration {
+                        report.calibrating.push(id);
                     }
-                    ActuatorType::RobStride04 => {
-                        Box::new(RobStride04Feedback::from_feedback_frame(feedback.clone()))
+                    if feedback.has_fault() {
+                        report.faulted.push(id);
                     }
-                };
+                }
+                _ => report.waiting_for_feedback.push(id),
+            }
+        }
 
-                feedback.angle = typed_feedback.angle_rad();
-                feedback.velocity = typed_feedback.velocity_rads();
-                feedback.torque = typed_feedback.torque_nm();
-
-                // Log feedback information
-                debug!("Motor {} feedback:", id);
-                debug!("  Angle: {:?}", feedback.angle);
-                debug!("  Velocity: {:?}", feedback.velocity);
-                debug!("  Torque: {:?}", feedback.torque);
-                debug!("  Temperature: {:?}", feedback.temperature);
-                debug!("  Faults:");
-                debug!("    Uncalibrated: {:?}", feedback.fault_uncalibrated);
-                debug!("    Hall encoding: {:?}", feedback.fault_hall_encoding);
-                debug!(
-                    "    Magnetic encoding: {:?}",
-                    feedback.fault_magnetic_encoding
-                );
-                debug!(
-                    "    Over temperature: {:?}",
-                    feedback.fault_over_temperature
-                );
-                debug!("    Overcurrent: {:?}", feedback.fault_overcurrent);
-                debug!("    Undervoltage: {:?}", feedback.fault_undervoltage);
+        report
+    }
 
-                feedback.angle = normalize_radians(feedback.angle).0;
-                return Ok(Some((feedback, record.state.last_feedback)));
-            }
+    /// Returns the target and latest feedback of an actuator, read under a
+    /// single lock so they belong together.
+    pub async fn snapshot(&self, id: u8) -> Result<ActuatorSnapshot> {
+        let actuators = self.actuators.read().await;
+        let record = actuators
+            .get(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        self.snapshot_of(id, record)
+    }
+
+    /// Returns the snapshots of all actuators, ordered by ID, read under a
+    /// single lock. Actuators that stopped responding keep their last feedback,
+    /// marked as stale, so a display doesn't lose them while they reconnect.
+    pub async fn snapshot_all(&self) -> Result<Vec<ActuatorSnapshot>> {
+        let actuators = self.actuators.read().await;
+        let mut ids: Vec<u8> = actuators.keys().copied().collect();
+        ids.sort_unstable();
+        ids.into_iter()
+            .map(|id| self.snapshot_of(id, &actuators[&id]))
+            .collect()
+    }
+
+    fn snapshot_of(&self, id: u8, record: &ActuatorRecord) -> Result<ActuatorSnapshot> {
+        let target = typed_command(
+            record.state.actuator_type,
+            record.state.control_command.clone(),
+        );
+        let feedback = record.state.feedback.clone().map(|feedback| {
+            let mut feedback = feedback_in_units(record.state.actuator_type, feedback);
+            feedback.angle = normalize_radians(feedback.angle).0;
+            feedback
+        });
+        let feedback_age = match feedback {
+            Some(_) => Some(record.state.last_feedback.elapsed()?),
+            None => None,
+        };
+
+        Ok(ActuatorSnapshot {
+            id,
+            enabled: record.state.enabled,
+            target_angle: normalize_radians(target.target_angle_rad()).0,
+            target_velocity: target.target_velocity_rads(),
+            target_torque: target.torque_nm(),
+            kp: target.kp(),
+            kd: target.kd(),
+            feedback,
+            feedback_age,
+            feedback_stale: feedback_age.is_some_and(|age| age > self.feedback_timeout),
+        })
+    }
+
+    /// Time since the latest feedback frame from the actuator was received,
+    /// None if none has been. A growing age means it stopped responding.
+    pub async fn feedback_age(&self, id: u8) -> Result<Option<Duration>> {
+        let actuators = self.actuators.read().await;
+        let record = actuators
+            .get(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        match record.state.feedback {
+            Some(_) => Ok(Some(record.state.last_feedback.elapsed()?)),
+            None => Ok(None),
         }
-        Ok(None)
     }
 
-    pub async fn zero(&mut self, id: u8) -> Result<()> {
-        let mut actuators = self.actuators.write().await;
+    /// Returns the latest position in degrees, normalized like `get_feedback`.
+    pub async fn get_position_deg(&self, id: u8) -> Result<Option<f32>> {
+        Ok(self
+            .get_feedback(id)
+            .await?
+            .map(|(feedback, _)| feedback.angle.to_degrees()))
+    }
+
+    /// Returns the temperature (Celsius) of each actuator, taken from the
+    /// latest feedback frame, which reports it with every reply. Actuators
+    /// with no feedback, or none within the feedback timeout, are omitted.
+    pub async fn get_temperatures(&self) -> HashMap<u8, f32> {
+        let actuators = self.actuators.read().await;
+        actuators
+            .iter()
+            .filter_map(|(&id, record)| {
+                let feedback = record.state.feedback.as_ref()?;
+                let age = record.state.last_feedback.elapsed().ok()?;
+                (age <= self.feedback_timeout).then_some((id, feedback.temperature))
+            })
+            .collect()
+    }
+
+    /// Continuous torque the actuator can sustain, in Nm.
+    pub async fn rated_torque(&self, id: u8) -> Result<f32> {
+        Ok(self.actuator_type(id).await?.torque_rating().rated)
+    }
+
+    /// Short-term peak torque of the actuator, in Nm.
+    pub async fn peak_torque(&self, id: u8) -> Result<f32> {
+        Ok(self.actuator_type(id).await?.torque_rating().peak)
+    }
+
+    async fn actuator_type(&self, id: u8) -> Result<ActuatorType> {
+        let actuators = self.actuators.read().await;
         let record = actuators
-            .get_mut(&id)
-            .ok_or_else(|| eyre::eyre!("Actuator not found"))?;
-        record.actuator.set_zero().await
+            .get(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        Ok(record.state.actuator_type)
     }
 
-    pub async fn change_id(&mut self, id: u8, new_id: u8) -> Result<()> {
-        let mut actuators = self.actuators.write().await;
-        let mut record = actuators
-            .remove(&id)
-            .ok_or_else(|| eyre::eyre!("Actuator not found"))?;
-        record.actuator.set_id(new_id).await?;
-        actuators.insert(new_id, record);
-        Ok(())
+    /// Returns whether the actuator has settled at its target, i.e. the latest
+    /// feedback is within `pos_tol` radians and `vel_tol` rad/s of the target.
+    /// Returns `false` if no feedback has been received yet.
+    pub async fn at_target(&self, id: u8, pos_tol: f32, vel_tol: f32) -> Result<bool> {
+        let actuators = self.actuators.read().await;
+        let record = actuators
+            .get(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+
+        let feedback = match record.state.feedback.clone() {
+            Some(feedback) => typed_feedback(record.state.actuator_type, feedback),
+            None => return Ok(false),
+        };
+        let target = typed_command(
+            record.state.actuator_type,
+            record.state.control_command.clone(),
+        );
+
+        Ok(
+            (feedback.angle_rad() - target.target_angle_rad()).abs() <= pos_tol
+                && (feedback.velocity_rads() - target.target_velocity_rads()).abs() <= vel_tol,
+        )
+    }
+
+    /// Bring-up check for the model's scaling limits: commands a move of
+    /// `offset` radians from the current position, waits `settle`, and compares
+    /// the observed displacement with the commanded one. Returns the observed
+    /// ratio if it differs from 1 by more than `tolerance`, which usually means
+    /// the actuator type is wrong. The previous target is restored afterwards,
+    /// also if the check fails after the move was commanded.
+    ///
+    /// Requires the actuator to be enabled and the run loop to be running.
+    pub async fn check_scaling(
+        &mut self,
+        id: u8,
+        offset: f32,
+        settle: Duration,
+        tolerance: f32,
+    ) -> Result<Option<f32>> {
+        if offset == 0.0 {
+            return Err(eyre::eyre!("Scaling check offset must be non-zero"));
+        }
+
+        let (start, _) = self
+            .get_feedback(id)
+            .await?
+            .ok_or_else(|| eyre::eyre!("No feedback from actuator {}", id))?;
+        let start = start.angle;
+        let previous = {
+            let actuators = self.actuators.read().await;
+            let record = actuators
+                .get(&id)
+                .ok_or(RobstrideError::ActuatorNotFound(id))?;
+            HashMap::from([(id, record.state.control_command.clone())])
+        };
+
+        // Soft limits may shorten the move
+        let (target, _, _) = self.command(id, start + offset, 0.0, 0.0).await?;
+        let commanded = normalize_radians(target - start).0;
+        if commanded == 0.0 {
+            self.restore_targets(&previous).await?;
+            return Err(eyre::eyre!(
+                "Soft limits leave no room to move actuator {} for the scaling check",
+                id
+            ));
+        }
+        time::sleep(settle).await;
+        let end = self.get_feedback(id).await;
+        self.restore_targets(&previous).await?;
+
+        let (end, _) = end?.ok_or_else(|| eyre::eyre!("No feedback from actuator {}", id))?;
+        let end = end.angle;
+        let ratio = normalize_radians(end - start).0 / commanded;
+        if (ratio - 1.0).abs() > tolerance {
+            warn!(
+                "Actuator {} moved {:.3}x the commanded offset; check its actuator type",
+                id, ratio
+            );
+            return Ok(Some(ratio));
+        }
+
+        Ok(None)
+    }
+
+    /// Classifies the last command the run loop sent to each actuator as
+    /// answered, unanswered or failed to send, based on the feedback received
+    /// so far. Actuators that haven't been commanded are left out.
+    pub async fn command_report(&self) -> CommandReport {
+        let mut report = CommandReport::default();
+        let actuators = self.actuators.read().await;
+        let mut ids: Vec<u8> = actuators.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            let state = &actuators[&id].state;
+            if state.last_send_failed {
+                report.send_errors.push(id);
+            } else if state.last_command_mode.is_none() {
+                continue;
+            } else if state.feedback.is_some() && state.last_feedback >= state.last_command {
+                report.responded.push(id);
+            } else {
+                report.no_response.push(id);
+            }
+        }
+        report
+    }
+
+    /// Pings one actuator `samples` times by reading its run mode parameter,
+    /// and reports how many replies timed out and the latency of the rest.
+    /// Sweeping every actuator in a chain points at the flaky connector.
+    pub async fn link_quality(&self, id: u8, samples: u32) -> Result<LinkQuality> {
+        if !self.actuators.read().await.contains_key(&id) {
+            return Err(RobstrideError::ActuatorNotFound(id).into());
+        }
+
+        let run_mode_index = RobStride04Parameter::RunMode.metadata().index;
+        let mut quality = LinkQuality {
+            samples,
+            ..Default::default()
+        };
+        let mut total_latency = Duration::ZERO;
+        for _ in 0..samples {
+            let sent = Instant::now();
+            match self.read_parameter(id, run_mode_index).await {
+                Ok(_) => {
+                    let latency = sent.elapsed();
+                    total_latency += latency;
+                    quality.max_latency = quality.max_latency.max(latency);
+                }
+                Err(e) => {
+                    debug!("Link probe of actuator {} failed: {}", id, e);
+                    quality.timeouts += 1;
+                }
+            }
+        }
+
+        let replies = samples - quality.timeouts;
+        if replies > 0 {
+            quality.mean_latency = total_latency / replies;
+        }
+        Ok(quality)
     }
-}
\ No newline at end of file
+
+    /// Reads a parameter from the actuator and waits for the reply, returning
+    /// the raw little-endian data word.
+    pub async fn read_parameter(&self, id: u8, param_index: u16) -> Result<u32> {
+        let requested = SystemTime::now();
+        {
+            let actuators = self.actuators.read().await;
+            let record = actuators
+                .get(&id)
+                .ok_or(RobstrideError::ActuatorNotFound(id))?;
+            record.actuator.read_parameter(param_index).await?;
+        }
+
+        let read_end = requested + PARAMETER_READ_TIMEOUT;
+        while SystemTime::now() < read_end {
+            {
+                let actuators = self.actuators.read().await;
+                if let Some(reading) = actuators
+                    .get(&id)
+                    .and_then(|record| record.state.parameters.get(&param_index))
+                {
+                    if reading.received >= requested {
+                        return Ok(reading.data);
+                    }
+                }
+            }
+            time::sleep(Duration::from_millis(1)).await;
+        }
+
+        Err(RobstrideError::Timeout {
+            id,
+            parameter_index: param_index,
+        }
+        .into())
+    }
+
+    /// Reads a float parameter from the actuator.
+    pub async fn read_parameter_f32(&self, id: u8, param_index: u16) -> Result<f32> {
+        Ok(f32::from_bits(self.read_parameter(id, param_index).await?))
+    }
+
+    /// Reads the present current (filtered Iq) and the current limit set by
+    /// `ControlConfig::max_current`, and returns their ratio. Values near 1.0
+    /// mean the actuator is close to current saturation, e.g. a jammed joint.
+    pub async fn current_utilization(&self, id: u8) -> Result<f32> {
+        let current = self
+            .read_parameter_f32(id, RobStride04Parameter::Iqf.metadata().index)
+            .await?;
+        let limit = self
+            .read_parameter_f32(id, RobStride04Parameter::LimitCur.metadata().index)
+            .await?;
+        if limit <= 0.0 {
+            return Err(eyre::eyre!(
+                "Actuator {} reports a current limit of {} A",
+                id,
+                limit
+            ));
+        }
+        Ok(current.abs() / limit)
+    }
+
+    /// Reads the bus voltage (`VBus`, volts) of every actuator.
+    pub async fn read_bus_voltage(&self) -> Result<HashMap<u8, f32>> {
+        self.read_parameter_all(RobStride04Parameter::VBus.metadata().index)
+            .await
+    }
+
+    /// Reads the filtered phase current (`Iqf`, amps) of every actuator.
+    pub async fn read_phase_current(&self) -> Result<HashMap<u8, f32>> {
+        self.read_parameter_all(RobStride04Parameter::Iqf.metadata().index)
+            .await
+    }
+
+    // Reads a float parameter from each actuator in turn
+    async fn read_parameter_all(&self, param_index: u16) -> Result<HashMap<u8, f32>> {
+        let mut values = HashMap::new();
+        for id in self.actuator_ids().await {
+            values.insert(id, self.read_parameter_f32(id, param_index).await?);
+        }
+        Ok(values)
+    }
+
+    /// Moves the actuator to `position` (radians, clamped to the soft limits
+    /// and the model's angle range) with the drive's internal position loop.
+    /// Switches the actuator to position mode first if needed and enables it,
+    /// then writes `kp` as the position loop gain (`LocKp`), `kd` as the
+    /// speed loop gain (`SpdKp`), which damps the move, and the target
+    /// (`Ref`). The slew rate is capped by `set_position_max_velocity`. While
+    /// in position mode the run loop only polls feedback; switch back with
+    /// `set_run_mode` to stream MIT commands again. Returns the position
+    /// written.
+    pub async fn position_control(
+        &mut self,
+        id: u8,
+        position: f32,
+        kp: f32,
+        kd: f32,
+    ) -> Result<f32> {
+        let limits = self.enter_run_mode(id, RunMode::Position).await?;
+
+        let mut actuators = self.actuators.write().await;
+        let record = actuators
+            .get_mut(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        let position = record
+            .state
+            .soft_limit_position(position.clamp(limits.min_angle, limits.max_angle));
+        write_runtime_parameter(record, RobStride04Parameter::LocKp.metadata().index, kp).await?;
+        write_runtime_parameter(record, RobStride04Parameter::SpdKp.metadata().index, kd).await?;
+        write_runtime_parameter(record, RobStride04Parameter::Ref.metadata().index, position)
+            .await?;
+        record.state.control_command.target_angle =
+            normalize_value(position, limits.min_angle, limits.max_angle, -100.0, 100.0);
+        record.state.target_time = Instant::now();
+        Ok(position)
+    }
+
+    /// Runs the actuator at `velocity` (rad/s, clamped to the soft limits and
+    /// the model's velocity range) with the drive's internal speed loop.
+    /// Switches the actuator to speed mode first if needed and enables it,
+    /// then writes `kd` as the speed loop gain (`SpdKp`) and the target
+    /// (`SpdRef`). While in speed mode the run loop only polls feedback;
+    /// switch back with `set_run_mode` to stream MIT commands again. Returns
+    /// the velocity written.
+    pub async fn speed_control(&mut self, id: u8, velocity: f32, kd: f32) -> Result<f32> {
+        let limits = self.enter_run_mode(id, RunMode::Speed).await?;
+
+        let mut actuators = self.actuators.write().await;
+        let record = actuators
+            .get_mut(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        let (_, velocity, _) = record.state.soft_limits.apply(0.0, velocity, 0.0);
+        let velocity = velocity.clamp(limits.min_velocity, limits.max_velocity);
+        write_runtime_parameter(record, RobStride04Parameter::SpdKp.metadata().index, kd).await?;
+        write_runtime_parameter(
+            record,
+            RobStride04Parameter::SpdRef.metadata().index,
+            velocity,
+        )
+        .await?;
+        record.state.control_command.target_velocity = normalize_value(
+            velocity,
+            limits.min_velocity,
+            limits.max_velocity,
+            -100.0,
+            100.0,
+        );
+        record.state.target_time = Instant::now();
+        Ok(velocity)
+    }
+
+    /// Drives the actuator with a direct Iq current command (`IqRef`, amps,
+    /// clamped to the model's current range), for torque control without the
+    /// drive's position or speed loops. Switches the actuator to current mode
+    /// first if needed and enables it. While in current mode the run loop only
+    /// polls feedback; switch back with `set_run_mode` to stream MIT commands
+    /// again.
+    pub async fn current_control(&mut self, id: u8, current: f32) -> Result<()> {
+        self.enter_run_mode(id, RunMode::Current).await?;
+
+        let mut actuators = self.actuators.write().await;
+        let record = actuators
+            .get_mut(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        let max_current = record.state.actuator_type.max_current();
+        write_runtime_parameter(
+            record,
+            RobStride04Parameter::IqRef.metadata().index,
+            current.clamp(-max_current, max_current),
+        )
+        .await
+    }
+
+    // Switches the actuator to `mode`, confirmed by reading it back, unless
+    // it is already there, and enables it. Returns the model's limits.
+    async fn enter_run_mode(&mut self, id: u8, mode: RunMode) -> Result<ActuatorMeasurementLimits> {
+        if *self.emergency_stopped.read().await {
+            return Err(RobstrideError::EmergencyStopped.into());
+        }
+        let (run_mode, enabled, limits) = {
+            let actuators = self.actuators.read().await;
+            let record = actuators
+                .get(&id)
+                .ok_or(RobstrideError::ActuatorNotFound(id))?;
+            (
+                record.state.run_mode,
+                record.state.enabled,
+                record.state.actuator_type.limits(),
+            )
+        };
+
+        if run_mode != Some(mode) && !self.set_run_mode(&[id], mode).await?.is_empty() {
+            return Err(eyre::eyre!(
+                "Actuator {} did not switch to {:?} mode",
+                id,
+                mode
+            ));
+        }
+        if !enabled {
+            self.enable(id).await?;
+        }
+        Ok(limits)
+    }
+
+    /// Reads the position mode speed limit (`LimitSpd`, rad/s), which caps
+    /// how fast the drive slews to a position target. Separate from the speed
+    /// mode target (`SpdRef`).
+    pub async fn position_max_velocity(&self, id: u8) -> Result<f32> {
+        self.read_parameter_f32(id, RobStride04Parameter::LimitSpd.metadata().index)
+            .await
+    }
+
+    /// Sets the position mode speed limit in rad/s, clamped to the model's
+    /// velocity range. Same as `ControlConfig::max_velocity`, and likewise
+    /// written again by `reapply_config`.
+    pub async fn set_position_max_velocity(&mut self, id: u8, velocity: f32) -> Result<()> {
+        let mut actuators = self.actuators.write().await;
+        let record = actuators
+            .get_mut(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+
+        let velocity = velocity.clamp(0.0, record.state.actuator_type.limits().max_velocity);
+        record.actuator.set_max_velocity(velocity).await?;
+        record.state.control_config.max_velocity = Some(velocity);
+        Ok(())
+    }
+
+    /// Returns whether the latest feedback reports the actuator in calibration
+    /// mode. Control commands are not sent while this is the case.
+    pub async fn is_calibrating(&self, id: u8) -> Result<bool> {
+        let actuators = self.actuators.read().await;
+        let record = actuators
+            .get(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        Ok(record
+            .state
+            .feedback
+            .as_ref()
+            .is_some_and(|feedback| feedback.mode == MotorMode::Calibration))
+    }
+
+    /// Returns the thermal headroom (0.0 to 1.0) of the actuator based on the
+    /// latest feedback temperature and its configured thermal limits.
+    pub async fn thermal_headroom(&self, id: u8) -> Result<Option<f32>> {
+        let actuators = self.actuators.read().await;
+        let record = actuators
+            .get(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        Ok(record.state.feedback.as_ref().map(|feedback| {
+            record
+                .state
+                .configuration
+                .thermal_limits
+                .headroom(feedback.temperature)
+        }))
+    }
+
+    /// Sets a function the run loop calls every cycle with the latest
+    /// positions (radians) of all actuators. The torques it returns (Nm) are
+    /// added to the commanded torque of the matching actuators, clamped to
+    /// their torque limits. `None` removes it.
+    pub async fn set_gravity_compensation(&self, compensation: Option<GravityCompensation>) {
+        *self.gravity_compensation.write().await = compensation;
+    }
+
+    /// Sets a function the run loop calls with the ID of an actuator when its
+    /// feedback times out, once per dropout; it is called again only after
+    /// feedback resumes and stops again. Runs while the run loop holds the
+    /// actuators lock, so it must not call back into the supervisor. `None`
+    /// removes it.
+    pub async fn set_disconnect_callback(&self, callback: Option<DisconnectCallback>) {
+        *self.disconnect_callback.write().await = callback;
+    }
+
+    /// Returns for each actuator whether it is connected, i.e. has sent
+    /// feedback within the feedback timeout.
+    pub async fn connection_status(&self) -> HashMap<u8, bool> {
+        let actuators = self.actuators.read().await;
+        actuators
+            .iter()
+            .map(|(&id, record)| {
+                let connected = record.state.feedback.is_some()
+                    && record
+                        .state
+                        .last_feedback
+                        .elapsed()
+                        .is_ok_and(|age| age <= self.feedback_timeout);
+                (id, connected)
+            })
+            .collect()
+    }
+
+    /// Scales kp, kd and torque of every command the run loop sends, e.g. to
+    /// soften all actuators at once. `scale` must be within 0.0 to 1.0.
+    pub async fn set_command_scale(&self, scale: f32) -> Result<()> {
+        self.ramp_command_scale(scale, scale, Duration::ZERO).await
+    }
+
+    /// Ramps the command scale linearly from `from` to `to` over `duration`,
+    /// e.g. for a soft start after enabling or reconnecting.
+    pub async fn ramp_command_scale(&self, from: f32, to: f32, duration: Duration) -> Result<()> {
+        if !(0.0..=1.0).contains(&from) || !(0.0..=1.0).contains(&to) {
+            return Err(eyre::eyre!("Command scale must be within 0.0 to 1.0"));
+        }
+        *self.command_scale.write().await = CommandScale {
+            from,
+            to,
+            start: Instant::now(),
+            duration,
+        };
+        Ok(())
+    }
+
+    /// Current command scale, see `set_command_scale`.
+    pub async fn command_scale(&self) -> f32 {
+        self.command_scale.read().await.value()
+    }
+
+    /// Brakes all actuators when no new target arrives within `timeout`, e.g.
+    /// if the controller sending them hangs. Targets count from `command`,
+    /// `command_all`, `move_relative`, `move_to`, `restore_targets` and each
+    /// row applied by `replay_recording`. Once tripped, the run loop sends
+    /// zero stiffness, velocity and torque with the configured kd, so joints
+    /// are damped to rest instead of holding or chasing a stale target. The
+    /// next target clears it. `None` turns the watchdog off, the default.
+    pub async fn set_command_watchdog(&self, timeout: Option<Duration>) {
+        let mut watchdog = self.command_watchdog.write().await;
+        watchdog.timeout = timeout;
+        watchdog.last_target = Instant::now();
+    }
+
+    /// Returns whether the command watchdog is braking the actuators, see
+    /// `set_command_watchdog`.
+    pub async fn watchdog_tripped(&self) -> bool {
+        self.command_watchdog.read().await.tripped
+    }
+
+    /// Returns whether the actuator's feedback shows sustained oscillation,
+    /// see `OscillationLimits`.
+    pub async fn is_oscillating(&self, id: u8) -> Result<bool> {
+        let actuators = self.actuators.read().await;
+        let record = actuators
+            .get(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        Ok(record.state.oscillating)
+    }
+
+    /// Returns whether the actuator's moving target went stale and hasn't
+    /// been replaced since, see `ActuatorConfiguration::max_target_age`.
+    pub async fn is_target_stale(&self, id: u8) -> Result<bool> {
+        let actuators = self.actuators.read().await;
+        let record = actuators
+            .get(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        Ok(record.state.stale_target == Some(record.state.target_time))
+    }
+
+    /// Enables scaling of kp, kd and torque by the thermal headroom, reducing
+    /// output as the actuator approaches its thermal limit.
+    pub async fn set_thermal_derating(&mut self, id: u8, enabled: bool) -> Result<()> {
+        let mut actuators = self.actuators.write().await;
+        let record = actuators
+            .get_mut(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        record.state.configuration.thermal_derating = enabled;
+        Ok(())
+    }
+
+    /// Sets how often the run loop commands an actuator, in Hz, e.g. lower
+    /// for a gripper than for leg joints. Cycles in between skip the actuator
+    /// entirely, leaving the bus to the others. Its feedback then only
+    /// arrives in reply to commands, so rates below two per feedback timeout
+    /// are rejected. None commands it every cycle. Same as
+    /// `ActuatorConfiguration::command_rate_hz`.
+    pub async fn set_command_rate(&mut self, id: u8, rate_hz: Option<f32>) -> Result<()> {
+        let mut actuators = self.actuators.write().await;
+        let record = actuators
+            .get_mut(&id)
+            .ok_or(RobstrideError::ActuatorNotFound(id))?;
+        let configuration = ActuatorConfiguration {
+            command_rate_hz: rate_hz,
+            ..record.state.configuration.clone()
+        };
+        configuration.validate()?;
+        self.check_command_rate(&configuration)?;
+        record.state.configuration = configuration;
+        Ok(())
+    }
+
+    // Rate-limited actuators are only polled when commanded, so a rate below
+    // two per feedback timeout would time out between commands, or after a
+    // single lost reply
+    fn check_command_rate(&self, configuration: &ActuatorConfiguration) -> Result<()> {
+        let Some(rate_hz) = configuration.command_rate_hz else {
+            return Ok(());
+        };
+        let min_rate_hz = 2.0 / self.feedback_timeout.as_secs_f32();
+        if rate_hz < min_rate_hz {
+            return Err(eyre::eyre!(
+                "command_rate_hz must be at least {} Hz with a feedback timeout of {:?}, got {}",
+                min_rate_hz,
+                self.feedback_timeout,
+                rate_hz
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sets the current position as the actuator's zero, then reads the
+    /// mechanical position (`MechPos`) back to confirm it took. The drive
+    /// occasionally ignores the command, leaving the position a full range
+    /// off, so it is sent again up to a few times before failing.
+    pub async fn zero(&mut self, id: u8) -> Result<()> {
+        let mut position = f32::NAN;
+        for attempt in 1..=ZERO_ATTEMPTS {
+            {
+                let actuators = self.actuators.read().await;
+                let record = actuators
+                    .get(&id)
+                    .ok_or(RobstrideError::ActuatorNotFound(id))?;
+                record.actuator.set_zero().await?;
+            }
+
+            position = self
+                .read_parameter_f32(id, RobStride04Parameter::MechPos.metadata().index)
+                .await?;
+            if position.abs() <= ZERO_TOLERANCE {
+                return Ok(());
+            }
+            warn!(
+                "Actuator {} reads {} rad after zeroing (attempt {})",
+                id, position, attempt
+            );
+        }
+
+        Err(eyre::eyre!(
+            "Actuator {} did not zero after {} attempts, position reads {} rad",
+            id,
+            ZERO_ATTEMPTS,
+            position
+        ))
+    }
+
+    /// Assigns the actuator a new CAN ID and waits for it to announce itself
+    /// under that ID. Fails without sending anything if `new_id` is already
+    /// used by another actuator. Once sent, the actuator is tracked under
+    /// `new_id` even if the announcement doesn't arrive in time.
+    pub async fn change_id(&mut self, id: u8, new_id: u8) -> Result<()> {
+        {
+            let mut actuators = self.actuators.write().await;
+            if !actuators.contains_key(&id) {
+                return Err(RobstrideError::ActuatorNotFound(id).into());
+            }
+            if new_id == id {
+                return Ok(());
+            }
+            if actuators.contains_key(&new_id) {
+                return Err(eyre::eyre!("Actuator ID {} is already in use", new_id));
+            }
+
+            self.discovered_ids
+                .write()
+                .await
+                .retain(|&discovered| discovered != new_id);
+            let mut record = actuators
+                .remove(&id)
+                .ok_or(RobstrideError::ActuatorNotFound(id))?;
+            let sent = record.actuator.set_id(new_id).await;
+            if let Err(e) = sent {
+                actuators.insert(id, record);
+                return Err(e);
+            }
+            actuators.insert(new_id, record);
+        }
+
+        let confirm_end = SystemTime::now() + CHANGE_ID_TIMEOUT;
+        while SystemTime::now() < confirm_end {
+            if self.discovered_ids.read().await.contains(&new_id) {
+                return Ok(());
+            }
+            time::sleep(Duration::from_millis(1)).await;
+        }
+        Err(eyre::eyre!(
+            "Actuator {} did not announce its new ID {}",
+            id,
+            new_id
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actuator::{get_index, get_value_le, put_index, put_value_le};
+    use crate::transport::{ch341_frame, CanPacket, MockTransport};
+    use crate::CommunicationType;
+
+    const HOST_ID: u8 = 0xfd;
+    // Zero position, velocity and torque at 28 °C
+    const FEEDBACK_DATA: [u8; 8] = [0x7f, 0xff, 0x7f, 0xff, 0x7f, 0xff, 0x01, 0x18];
+
+    // Supervisor with RobStride04s on a mock transport, for scripting the
+    // bus and inspecting what was sent
+    async fn mock_supervisor(ids: &[u8]) -> (Supervisor, MockTransport) {
+        let supervisor = Supervisor::new(Duration::from_secs(1)).unwrap();
+        let mock = MockTransport::new();
+        supervisor
+            .add_transport("mock".to_string(), TransportType::Mock(mock.clone()))
+            .await
+            .unwrap();
+        let tx = supervisor.get_transport_tx("mock").await.unwrap();
+        for &id in ids {
+            supervisor
+                .add_actuator(
+                    Box::new(RobStride04::new(id, HOST_ID, tx.clone())),
+                    ActuatorConfiguration::default(),
+                )
+                .await
+                .unwrap();
+        }
+        (supervisor, mock)
+    }
+
+    // Waits for the transport task to hand at least `count` frames to the mock
+    async fn sent_frames(mock: &MockTransport, count: usize) -> Vec<CanPacket> {
+        time::timeout(Duration::from_secs(1), async {
+            loop {
+                let sent = mock.sent();
+                if sent.len() >= count {
+                    return sent;
+                }
+                time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("frames were not sent")
+    }
+
+    fn error_kind<T: std::fmt::Debug>(result: Result<T>) -> RobstrideError {
+        result
+            .unwrap_err()
+            .downcast_ref::<RobstrideError>()
+            .expect("not a RobstrideError")
+            .clone()
+    }
+
+    fn communication_type(packet: &CanPacket) -> u32 {
+        packet.id >> 24
+    }
+
+    // Index and raw value of each parameter write sent, oldest first
+    fn parameter_writes(sent: &[CanPacket]) -> Vec<(u16, u32)> {
+        sent.iter()
+            .filter(|packet| communication_type(packet) == CommunicationType::Write as u32)
+            .map(|packet| (get_index(&packet.data), get_value_le(&packet.data, 4)))
+            .collect()
+    }
+
+    // Answers parameter reads like a drive: with the last value written to
+    // the parameter, else the one in `registers`, else zero
+    fn fake_drive(mock: &MockTransport, mut registers: HashMap<u16, u32>) {
+        let mock = mock.clone();
+        tokio::spawn(async move {
+            let mut handled = 0;
+            loop {
+                let sent = mock.sent();
+                for packet in &sent[handled..] {
+                    let index = get_index(&packet.data);
+                    match communication_type(packet) {
+                        t if t == CommunicationType::Write as u32 => {
+                            registers.insert(index, get_value_le(&packet.data, 4));
+                        }
+                        t if t == CommunicationType::Read as u32 => {
+                            // Replies carry the motor ID in the host ID field
+                            let motor_id = packet.id & 0xff;
+                            let mut data = [0; 8];
+                            put_index(&mut data, index);
+                            put_value_le(&mut data, 4, registers.get(&index).copied().unwrap_or(0));
+                            mock.push_frame(
+                                (CommunicationType::Read as u32) << 24
+                                    | motor_id << 8
+                                    | HOST_ID as u32,
+                                &data,
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+                handled = sent.len();
+                time::sleep(Duration::from_millis(1)).await;
+            }
+        });
+    }
+
+    // Feeds a feedback frame from actuator `id` and waits until the
+    // supervisor has processed it
+    async fn push_feedback(supervisor: &Supervisor, mock: &MockTransport, id: u8, mode: MotorMode) {
+        let received = || async {
+            supervisor.actuators.read().await[&id]
+                .state
+                .messages_received
+        };
+        let before = received().await;
+        let can_id = (CommunicationType::Feedback as u32) << 24
+            | (mode as u32) << 22
+            | (id as u32) << 8
+            | HOST_ID as u32;
+        mock.push_frame(can_id, &FEEDBACK_DATA);
+        time::timeout(Duration::from_secs(1), async {
+            while received().await == before {
+                time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("feedback was not processed");
+    }
+
+    #[tokio::test]
+    async fn second_shutdown_returns_at_once() {
+        let (mut supervisor, mock) = mock_supervisor(&[1]).await;
+        supervisor.enable(1).await.unwrap();
+
+        let mut clone = supervisor.clone_controller();
+        time::timeout(Duration::from_secs(1), async {
+            supervisor.shutdown().await.unwrap();
+            clone.shutdown().await.unwrap();
+            supervisor.shutdown().await.unwrap();
+        })
+        .await
+        .expect("shutdown hung");
+
+        time::sleep(Duration::from_millis(20)).await;
+        let stops = sent_frames(&mock, 2)
+            .await
+            .iter()
+            .filter(|packet| communication_type(packet) == CommunicationType::Stop as u32)
+            .count();
+        assert_eq!(stops, 1);
+    }
+
+    #[tokio::test]
+    async fn command_all_enables_nothing_if_any_actuator_cannot_start() {
+        let (supervisor, mock) = mock_supervisor(&[1, 2]).await;
+        supervisor
+            .actuators
+            .write()
+            .await
+            .get_mut(&1)
+            .unwrap()
+            .state
+            .configuration
+            .auto_start = true;
+        push_feedback(&supervisor, &mock, 1, MotorMode::Reset).await;
+        push_feedback(&supervisor, &mock, 2, MotorMode::Reset).await;
+
+        let targets = HashMap::from([(1, (0.5, 0.0, 0.0)), (2, (0.5, 0.0, 0.0))]);
+        let error = supervisor
+            .clone_controller()
+            .command_all(&targets)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<RobstrideError>(),
+            Some(RobstrideError::UnexpectedMode { id: 2, .. })
+        ));
+
+        time::sleep(Duration::from_millis(20)).await;
+        assert!(mock.sent().is_empty());
+        assert!(!supervisor.actuators.read().await[&1].state.enabled);
+    }
+
+    #[tokio::test]
+    async fn unknown_ids_are_not_found() {
+        let (mut supervisor, mock) = mock_supervisor(&[1]).await;
+        let not_found = RobstrideError::ActuatorNotFound(7);
+
+        assert_eq!(error_kind(supervisor.enable(7).await), not_found);
+        assert_eq!(error_kind(supervisor.disable(7, false).await), not_found);
+        assert_eq!(
+            error_kind(supervisor.command(7, 0.0, 0.0, 0.0).await),
+            not_found
+        );
+        let targets = HashMap::from([(1, (0.0, 0.0, 0.0)), (7, (0.0, 0.0, 0.0))]);
+        assert_eq!(
+            error_kind(supervisor.command_all(&targets).await),
+            not_found
+        );
+        assert_eq!(error_kind(supervisor.get_feedback(7).await), not_found);
+        assert_eq!(
+            error_kind(supervisor.read_parameter(7, 0x7005).await),
+            not_found
+        );
+        assert_eq!(
+            error_kind(supervisor.get_transport_tx("missing").await),
+            RobstrideError::TransportNotFound("missing".to_string())
+        );
+
+        time::sleep(Duration::from_millis(20)).await;
+        assert!(mock.sent().is_empty());
+    }
+
+    #[tokio::test]
+    async fn feedback_and_commands_round_trip() {
+        let (mut supervisor, mock) = mock_supervisor(&[1]).await;
+
+        // A quarter turn, 7.5 rad/s and -60 Nm at 28 °C, in run mode
+        let data = [0x8f, 0xff, 0xc0, 0x00, 0x40, 0x00, 0x01, 0x18];
+        mock.push_frame(0x0280_01fd, &data);
+        let feedback = time::timeout(Duration::from_secs(1), async {
+            loop {
+                if let Some((feedback, _)) = supervisor.get_feedback(1).await.unwrap() {
+                    return feedback;
+                }
+                time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("feedback was not processed");
+        assert!((feedback.angle - std::f32::consts::FRAC_PI_2).abs() < 1e-3);
+        assert!((feedback.velocity - 7.5).abs() < 1e-3);
+        assert!((feedback.torque + 60.0).abs() < 1e-2);
+        assert_eq!(feedback.temperature, 28.0);
+        assert_eq!(feedback.mode, MotorMode::Run);
+
+        let cmd = RobStride04Command {
+            target_angle_rad: 0.0,
+            target_velocity_rads: 0.0,
+            kp: 100.0,
+            kd: 5.0,
+            torque_nm: 0.0,
+        };
+        supervisor
+            .control(1, cmd.to_control_command())
+            .await
+            .unwrap();
+        let sent = sent_frames(&mock, 1).await;
+        assert_eq!(
+            ch341_frame(sent[0].id, &sent[0].data),
+            [
+                b'A', b'T', 0x0b, 0xff, 0xf8, 0x0c, 0x08, 0x7f, 0xff, 0x7f, 0xff, 0x05, 0x1e, 0x0c,
+                0xcc, b'\r', b'\n'
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn speed_control_writes_the_mode_then_the_velocity() {
+        let (mut supervisor, mock) = mock_supervisor(&[1]).await;
+        fake_drive(&mock, HashMap::new());
+
+        supervisor.speed_control(1, 20.0, 2.0).await.unwrap();
+
+        // Mode write, its read back, enable, then the two speed loop writes
+        let sent = sent_frames(&mock, 5).await;
+        let types: Vec<u32> = sent.iter().map(communication_type).collect();
+        assert_eq!(
+            types,
+            [
+                CommunicationType::Write as u32,
+                CommunicationType::Read as u32,
+                CommunicationType::Enable as u32,
+                CommunicationType::Write as u32,
+                CommunicationType::Write as u32,
+            ]
+        );
+        // The velocity is clamped to the RobStride04's 15 rad/s
+        assert_eq!(
+            parameter_writes(&sent),
+            [
+                (0x7005, RunMode::Speed as u32),
+                (0x701f, 2.0f32.to_bits()),
+                (0x700a, 15.0f32.to_bits()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn current_control_writes_the_mode_then_the_current() {
+        let (mut supervisor, mock) = mock_supervisor(&[1]).await;
+        fake_drive(&mock, HashMap::new());
+
+        // Clamped to the RobStride04's 90 A
+        supervisor.current_control(1, -100.0).await.unwrap();
+        let sent = sent_frames(&mock, 4).await;
+        assert_eq!(
+            parameter_writes(&sent),
+            [
+                (0x7005, RunMode::Current as u32),
+                (0x7006, (-90.0f32).to_bits()),
+            ]
+        );
+
+        // Already in current mode, so only the reference is written
+        mock.clear_sent();
+        supervisor.current_control(1, 12.5).await.unwrap();
+        let sent = sent_frames(&mock, 1).await;
+        assert_eq!(parameter_writes(&sent), [(0x7006, 12.5f32.to_bits())]);
+        assert_eq!(
+            ch341_frame(sent[0].id, &sent[0].data),
+            [
+                b'A', b'T', 0x90, 0x07, 0xe8, 0x0c, 0x08, 0x06, 0x70, 0x00, 0x00, 0x00, 0x00, 0x48,
+                0x41, b'\r', b'\n'
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn bus_voltage_and_phase_current_are_read_from_registers() {
+        let (supervisor, mock) = mock_supervisor(&[1, 2]).await;
+        fake_drive(
+            &mock,
+            HashMap::from([(0x701c, 48.5f32.to_bits()), (0x701a, (-2.25f32).to_bits())]),
+        );
+
+        assert_eq!(
+            supervisor.read_bus_voltage().await.unwrap(),
+            HashMap::from([(1, 48.5), (2, 48.5)])
+        );
+        assert_eq!(
+            supervisor.read_phase_current().await.unwrap(),
+            HashMap::from([(1, -2.25), (2, -2.25)])
+        );
+    }
+
+    #[tokio::test]
+    async fn soft_limits_hold_for_the_commands_sent() {
+        let (mut supervisor, mock) = mock_supervisor(&[1]).await;
+        supervisor
+            .set_soft_limits(
+                1,
+                SoftLimits {
+                    position: Some((-0.5, 0.5)),
+                    max_velocity: Some(1.0),
+                    max_torque: Some(10.0),
+                },
+            )
+            .await
+            .unwrap();
+        supervisor.enable(1).await.unwrap();
+        for _ in 0..5 {
+            push_feedback(&supervisor, &mock, 1, MotorMode::Run).await;
+        }
+
+        assert_eq!(
+            supervisor.command(1, 0.8, 3.0, -5.0).await.unwrap(),
+            (0.5, 1.0, -5.0)
+        );
+        assert_eq!(
+            supervisor.command(1, 0.4, 3.0, 5.0).await.unwrap(),
+            (0.4, 1.0, 5.0)
+        );
+
+        // Extrapolation carries the target past 0.5 rad within 0.1 s, and
+        // compensation adds 50 Nm
+        supervisor
+            .actuators
+            .write()
+            .await
+            .get_mut(&1)
+            .unwrap()
+            .state
+            .configuration
+            .target_extrapolation = Some(Duration::from_secs(1));
+        supervisor
+            .set_gravity_compensation(Some(Box::new(|_| HashMap::from([(1, 50.0)]))))
+            .await;
+        mock.clear_sent();
+        let mut run_loop = supervisor.clone_controller();
+        let run_loop = tokio::spawn(async move { run_loop.run(Duration::from_millis(5)).await });
+        time::sleep(Duration::from_millis(300)).await;
+        run_loop.abort();
+
+        let control = mock
+            .sent()
+            .into_iter()
+            .rev()
+            .find(|packet| communication_type(packet) == CommunicationType::Control as u32)
+            .expect("no control frame sent");
+        let limits = ActuatorType::RobStride04.limits();
+        let raw_angle = u16::from_be_bytes([control.data[0], control.data[1]]) as f32;
+        let raw_torque = ((control.id >> 8) & 0xffff) as f32;
+        let angle = normalize_value(raw_angle, 0.0, 65535.0, limits.min_angle, limits.max_angle);
+        let torque = normalize_value(
+            raw_torque,
+            0.0,
+            65535.0,
+            limits.min_torque,
+            limits.max_torque,
+        );
+        assert!((angle - 0.5).abs() < 1e-3, "angle {}", angle);
+        assert!((torque - 10.0).abs() < 1e-2, "torque {}", torque);
+    }
+
+    #[tokio::test]
+    async fn command_rate_below_two_per_feedback_timeout_is_rejected() {
+        let (mut supervisor, _mock) = mock_supervisor(&[1]).await;
+        let tx = supervisor.get_transport_tx("mock").await.unwrap();
+        let slow = ActuatorConfiguration {
+            command_rate_hz: Some(1.0),
+            ..Default::default()
+        };
+        assert!(supervisor
+            .add_actuator(Box::new(RobStride04::new(2, HOST_ID, tx)), slow)
+            .await
+            .is_err());
+
+        assert!(supervisor.set_command_rate(1, Some(1.5)).await.is_err());
+        supervisor.set_command_rate(1, Some(2.0)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn commands_after_emergency_stop_are_refused() {
+        let (mut supervisor, mock) = mock_supervisor(&[1]).await;
+        supervisor.enable(1).await.unwrap();
+        for _ in 0..5 {
+            push_feedback(&supervisor, &mock, 1, MotorMode::Run).await;
+        }
+        supervisor
+            .configure(
+                1,
+                ControlConfig {
+                    kp: 50.0,
+                    kd: 2.0,
+                    max_torque: None,
+                    max_velocity: None,
+                    max_current: None,
+                },
+            )
+            .await
+            .unwrap();
+        let mut run_loop = supervisor.clone_controller();
+        let run_loop = tokio::spawn(async move { run_loop.run(Duration::from_millis(5)).await });
+        supervisor.command(1, 0.3, 0.0, 0.0).await.unwrap();
+        time::sleep(Duration::from_millis(20)).await;
+
+        supervisor.emergency_stop().await.unwrap();
+        time::sleep(Duration::from_millis(20)).await;
+        mock.clear_sent();
+        assert_eq!(
+            error_kind(supervisor.command(1, 0.6, 1.0, 5.0).await),
+            RobstrideError::EmergencyStopped
+        );
+        assert_eq!(
+            error_kind(supervisor.position_control(1, 0.6, 10.0, 1.0).await),
+            RobstrideError::EmergencyStopped
+        );
+        assert_eq!(
+            error_kind(supervisor.speed_control(1, 1.0, 1.0).await),
+            RobstrideError::EmergencyStopped
+        );
+        assert_eq!(
+            error_kind(supervisor.current_control(1, 2.0).await),
+            RobstrideError::EmergencyStopped
+        );
+        let snapshot = supervisor.snapshot_targets().await;
+        assert_eq!(
+            error_kind(supervisor.restore_targets(&snapshot).await),
+            RobstrideError::EmergencyStopped
+        );
+        assert_eq!(
+            error_kind(supervisor.with_gains(1, 80.0, 3.0, |_| async {}).await),
+            RobstrideError::EmergencyStopped
+        );
+        time::sleep(Duration::from_millis(50)).await;
+        run_loop.abort();
+
+        // Only feedback polls, no command with stiffness, damping or torque
+        let sent = mock.sent();
+        assert!(!sent.is_empty());
+        assert!(sent
+            .iter()
+            .all(|packet| communication_type(packet) == CommunicationType::Feedback as u32));
+        let snapshot = supervisor.snapshot(1).await.unwrap();
+        assert!(
+            (snapshot.target_angle - 0.3).abs() < 1e-3,
+            "target {}",
+            snapshot.target_angle
+        );
+        assert_eq!((snapshot.kp, snapshot.kd), (0.0, 0.0));
+    }
+
+    #[tokio::test]
+    async fn restore_targets_cancels_moves_and_playback() {
+        let (mut supervisor, mock) = mock_supervisor(&[1, 2]).await;
+        for id in [1, 2] {
+            supervisor.enable(id).await.unwrap();
+            push_feedback(&supervisor, &mock, id, MotorMode::Run).await;
+        }
+        let snapshot = supervisor.snapshot_targets().await;
+
+        supervisor.move_to(1, 2.0, 1.0).await.unwrap();
+        supervisor.profiled_move(2, 2.0, 1.0, 1.0).await.unwrap();
+        let only_first: HashMap<u8, ControlCommand> = HashMap::from([(1, snapshot[&1].clone())]);
+        supervisor.restore_targets(&only_first).await.unwrap();
+        assert!(supervisor.is_move_complete(1).await.unwrap());
+        assert!(supervisor.is_playing().await);
+
+        supervisor.restore_targets(&snapshot).await.unwrap();
+        assert!(!supervisor.is_playing().await);
+    }
+
+    #[tokio::test]
+    async fn replayed_and_restored_targets_feed_the_watchdog() {
+        let (mut supervisor, _mock) = mock_supervisor(&[1]).await;
+        let path = std::env::temp_dir().join(format!("watchdog-replay-{}.csv", std::process::id()));
+        let mut csv = String::from("time,kind,id,position,velocity,torque,kp,kd\n");
+        for row in 0..=10 {
+            csv += &format!("{},target,1,0.0,0.0,0.0,10.0,1.0\n", row as f32 * 0.02);
+        }
+        std::fs::write(&path, csv).unwrap();
+
+        supervisor
+            .set_command_watchdog(Some(Duration::from_millis(40)))
+            .await;
+        let mut run_loop = supervisor.clone_controller();
+        let run_loop = tokio::spawn(async move { run_loop.run(Duration::from_millis(5)).await });
+        let watcher = supervisor.clone_controller();
+        let watch = tokio::spawn(async move {
+            let end = time::Instant::now() + Duration::from_millis(200);
+            while time::Instant::now() < end {
+                if watcher.watchdog_tripped().await {
+                    return true;
+                }
+                time::sleep(Duration::from_millis(2)).await;
+            }
+            false
+        });
+        supervisor.replay_recording(&path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(!watch.await.unwrap(), "watchdog tripped during replay");
+
+        time::sleep(Duration::from_millis(80)).await;
+        assert!(supervisor.watchdog_tripped().await);
+        let snapshot = supervisor.snapshot_targets().await;
+        supervisor.restore_targets(&snapshot).await.unwrap();
+        time::sleep(Duration::from_millis(15)).await;
+        assert!(!supervisor.watchdog_tripped().await);
+        run_loop.abort();
+    }
+
+    #[tokio::test]
+    async fn with_gains_leaves_gains_zero_after_an_emergency_stop() {
+        let (mut supervisor, mock) = mock_supervisor(&[1]).await;
+        supervisor.enable(1).await.unwrap();
+        push_feedback(&supervisor, &mock, 1, MotorMode::Run).await;
+        supervisor
+            .configure(
+                1,
+                ControlConfig {
+                    kp: 50.0,
+                    kd: 2.0,
+                    max_torque: None,
+                    max_velocity: None,
+                    max_current: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        supervisor
+            .with_gains(1, 80.0, 3.0, |controller| async move {
+                controller.emergency_stop().await.unwrap();
+            })
+            .await
+            .unwrap();
+
+        let snapshot = supervisor.snapshot(1).await.unwrap();
+        assert_eq!((snapshot.kp, snapshot.kd), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,230 @@
+use eyre::{Result, WrapErr};
+use std::path::Path;
+
+// Spacing of the rows of a generated profile, in seconds
+const PROFILE_STEP: f32 = 0.01;
+
+/// A joint trajectory loaded from a CSV file.
+///
+/// The first row is a header naming the actuator ID of each column after the
+/// timestamp, e.g. `time,1,2,3`. Each following row holds a timestamp in
+/// seconds and one position in radians per actuator. Timestamps must be
+/// increasing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trajectory {
+    ids: Vec<u8>,
+    times: Vec<f32>,
+    positions: Vec<Vec<f32>>,
+}
+
+impl Trajectory {
+    pub fn from_csv(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read trajectory {}", path.display()))?;
+        Self::parse_csv(&contents)
+    }
+
+    pub fn parse_csv(contents: &str) -> Result<Self> {
+        let mut lines = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+
+        let header = lines
+            .next()
+            .ok_or_else(|| eyre::eyre!("Trajectory is empty"))?;
+        let ids = header
+            .split(',')
+            .skip(1)
+            .map(|id| {
+                id.trim()
+                    .parse::<u8>()
+                    .wrap_err_with(|| format!("invalid actuator ID in header: {:?}", id))
+            })
+            .collect::<Result<Vec<u8>>>()?;
+        if ids.is_empty() {
+            return Err(eyre::eyre!("Trajectory header has no actuator columns"));
+        }
+
+        let mut times = Vec::new();
+        let mut positions = Vec::new();
+        for (row, line) in lines.enumerate() {
+            let values = line
+                .split(',')
+                .map(|value| value.trim().parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .wrap_err_with(|| format!("invalid value in trajectory row {}", row + 1))?;
+            times.push(values[0]);
+            positions.push(values[1..].to_vec());
+        }
+
+        Self::new(ids, times, positions)
+    }
+
+    /// Builds a trajectory from one timestamp and one row of positions per
+    /// step, with a position per ID in each row.
+    pub fn new(ids: Vec<u8>, times: Vec<f32>, positions: Vec<Vec<f32>>) -> Result<Self> {
+        if ids.is_empty() {
+            return Err(eyre::eyre!("Trajectory has no actuator columns"));
+        }
+        if times.is_empty() {
+            return Err(eyre::eyre!("Trajectory has no rows"));
+        }
+        if times.len() != positions.len() {
+            return Err(eyre::eyre!(
+                "Trajectory has {} timestamps but {} rows",
+                times.len(),
+                positions.len()
+            ));
+        }
+        for (row, row_positions) in positions.iter().enumerate() {
+            if row_positions.len() != ids.len() {
+                return Err(eyre::eyre!(
+                    "Trajectory row {} has {} columns, expected {}",
+                    row + 1,
+                    row_positions.len() + 1,
+                    ids.len() + 1
+                ));
+            }
+        }
+        if let Some(row) = times.windows(2).position(|pair| pair[1] <= pair[0]) {
+            return Err(eyre::eyre!(
+                "Trajectory timestamps must increase (row {})",
+                row + 2
+            ));
+        }
+
+        Ok(Self {
+            ids,
+            times,
+            positions,
+        })
+    }
+
+    /// Builds a trapezoidal move of one actuator from `start` to `end`
+    /// (radians): accelerating at `max_acceleration` (rad/s^2) up to
+    /// `max_velocity` (rad/s), cruising, then decelerating to rest at `end`.
+    /// Moves too short to reach `max_velocity` use a triangular profile.
+    pub fn trapezoidal(
+        id: u8,
+        start: f32,
+        end: f32,
+        max_velocity: f32,
+        max_acceleration: f32,
+    ) -> Result<Self> {
+        if !(max_velocity.is_finite() && max_velocity > 0.0) {
+            return Err(eyre::eyre!("Maximum velocity must be positive"));
+        }
+        if !(max_acceleration.is_finite() && max_acceleration > 0.0) {
+            return Err(eyre::eyre!("Maximum acceleration must be positive"));
+        }
+
+        let distance = (end - start).abs();
+        let direction = (end - start).signum();
+        // Peak velocity, lower than the maximum when there's no room to cruise
+        let peak = max_velocity.min((distance * max_acceleration).sqrt());
+        let accel_time = peak / max_acceleration;
+        let accel_distance = 0.5 * peak * accel_time;
+        let cruise_time = if peak > 0.0 {
+            (distance - 2.0 * accel_distance) / peak
+        } else {
+            0.0
+        };
+        let total = 2.0 * accel_time + cruise_time;
+
+        let offset = |t: f32| {
+            if t < accel_time {
+                0.5 * max_acceleration * t * t
+            } else if t < accel_time + cruise_time {
+                accel_distance + peak * (t - accel_time)
+            } else {
+                let remaining = total - t;
+                distance - 0.5 * max_acceleration * remaining * remaining
+            }
+        };
+
+        let steps = ((total / PROFILE_STEP).ceil() as usize).max(1);
+        let mut times = Vec::with_capacity(steps + 1);
+        let mut positions = Vec::with_capacity(steps + 1);
+        for step in 0..steps {
+            let t = step as f32 * PROFILE_STEP;
+            if step > 0 && t >= total {
+                break;
+            }
+            times.push(t);
+            positions.push(vec![start + direction * offset(t)]);
+        }
+        let last = times[times.len() - 1];
+        times.push(if total > last {
+            total
+        } else {
+            last + PROFILE_STEP
+        });
+        positions.push(vec![end]);
+
+        Self::new(vec![id], times, positions)
+    }
+
+    pub fn ids(&self) -> &[u8] {
+        &self.ids
+    }
+
+    /// Time of the last row, relative to the first.
+    pub fn duration(&self) -> f32 {
+        self.times[self.times.len() - 1] - self.times[0]
+    }
+
+    /// Returns `(id, position, velocity)` for each actuator at `t` seconds
+    /// from the start, interpolating linearly between rows. The velocity is
+    /// the slope of the current segment and is zero outside the trajectory.
+    pub fn sample(&self, t: f32) -> Vec<(u8, f32, f32)> {
+        let t = self.times[0] + t;
+        let next = self.times.partition_point(|&time| time <= t);
+
+        if next == 0 || next == self.times.len() {
+            let row = if next == 0 { 0 } else { self.times.len() - 1 };
+            return self
+                .ids
+                .iter()
+                .zip(&self.positions[row])
+                .map(|(&id, &position)| (id, position, 0.0))
+                .collect();
+        }
+
+        let (t0, t1) = (self.times[next - 1], self.times[next]);
+        let alpha = (t - t0) / (t1 - t0);
+        self.ids
+            .iter()
+            .zip(self.positions[next - 1].iter().zip(&self.positions[next]))
+            .map(|(&id, (&p0, &p1))| (id, p0 + (p1 - p0) * alpha, (p1 - p0) / (t1 - t0)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trapezoidal_profile_ramps_cruises_and_holds() {
+        // 2 rad at up to 1 rad/s and 2 rad/s^2: 0.5 s ramps around a 1.5 s
+        // cruise
+        let profile = Trajectory::trapezoidal(1, 0.0, 2.0, 1.0, 2.0).unwrap();
+        assert!((profile.duration() - 2.5).abs() < 1e-4);
+        let (_, position, velocity) = profile.sample(1.25)[0];
+        assert!((position - 1.0).abs() < 1e-3);
+        assert!((velocity - 1.0).abs() < 1e-3);
+        assert_eq!(profile.sample(3.0)[0], (1, 2.0, 0.0));
+    }
+
+    #[test]
+    fn short_trapezoidal_moves_are_triangles() {
+        // Too short to reach the velocity limit: a triangle peaking at
+        // sqrt(0.5) rad/s
+        let profile = Trajectory::trapezoidal(1, 0.0, -0.25, 1.0, 2.0).unwrap();
+        assert!((profile.duration() - 2.0 * 0.5f32.sqrt() / 2.0).abs() < 1e-4);
+        assert_eq!(profile.sample(1.0)[0], (1, -0.25, 0.0));
+        assert!(Trajectory::trapezoidal(1, 0.0, 0.0, 1.0, 2.0).is_ok());
+    }
+}
@@ -1,25 +1,97 @@
+use crate::RobstrideError;
 use eyre::Error;
 use socketcan::async_std::CanSocket;
 use socketcan::{EmbeddedFrame, ExtendedId};
-use std::sync::Arc;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::sync::Mutex as TokioMutex;
-use tokio_serial::{SerialPortBuilderExt, SerialStream};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::sync::{Mutex as TokioMutex, Notify};
+use tokio_serial::{ClearBuffer, SerialPort, SerialPortBuilderExt, SerialStream};
+
+pub use tokio_serial::{FlowControl, StopBits};
 
 /// Result type for send operations
 type SendResult = Result<(), Error>;
 /// Result type for receive operations
-type RecvResult = Result<(u32, Vec<u8>), Error>;
+type RecvResult = Result<CanPacket, Error>;
 /// Future type for send operations
 type SendFuture<'a> = std::pin::Pin<Box<dyn std::future::Future<Output = SendResult> + Send + 'a>>;
 /// Future type for receive operations
 type RecvFuture<'a> = std::pin::Pin<Box<dyn std::future::Future<Output = RecvResult> + Send + 'a>>;
+/// Future type for flush operations
+type FlushFuture<'a> = std::pin::Pin<Box<dyn std::future::Future<Output = SendResult> + Send + 'a>>;
+
+/// Framing errors on the receive side of a transport. Only byte-stream
+/// transports (CH341) can see these; others receive whole frames.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RxStats {
+    // Candidate frames rejected for a bad length or terminator
+    pub invalid_frames: u64,
+    // Bytes skipped while looking for the start of a frame
+    pub discarded_bytes: u64,
+    // Times the receive buffer filled without a valid frame and was dropped
+    // to resync. A steadily rising count points at a baud rate mismatch.
+    pub resyncs: u64,
+}
+
+/// Serial settings of a CH341 adapter. The defaults (921600 baud, one stop
+/// bit, no flow control) suit the stock adapter; some clones only frame
+/// reliably with RTS/CTS flow control or two stop bits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SerialConfig {
+    pub baud_rate: u32,
+    pub flow_control: FlowControl,
+    pub stop_bits: StopBits,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self {
+            baud_rate: 921600,
+            flow_control: FlowControl::None,
+            stop_bits: StopBits::One,
+        }
+    }
+}
+
+/// A received CAN frame.
+///
+/// CAN payloads are at most 8 bytes, so the data is stored inline and `len`
+/// gives the number of valid bytes. This replaces the `(u32, Vec<u8>)` tuple
+/// previously returned by `Transport::recv`; use `data()` where a slice was
+/// used before.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CanPacket {
+    pub id: u32,
+    pub data: [u8; 8],
+    pub len: u8,
+}
+
+impl CanPacket {
+    /// Creates a packet, truncating `data` to 8 bytes.
+    pub fn new(id: u32, data: &[u8]) -> Self {
+        let len = data.len().min(8);
+        let mut buf = [0u8; 8];
+        buf[..len].copy_from_slice(&data[..len]);
+        Self {
+            id,
+            data: buf,
+            len: len as u8,
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
 
 #[derive(Clone)]
 pub enum TransportType {
     CH341(CH341Transport),
     SocketCAN(SocketCanTransport),
     Stub(StubTransport),
+    Mock(MockTransport),
 }
 
 impl Transport for TransportType {
@@ -28,6 +100,7 @@ impl Transport for TransportType {
             TransportType::CH341(t) => t.kind(),
             TransportType::SocketCAN(t) => t.kind(),
             TransportType::Stub(t) => t.kind(),
+            TransportType::Mock(t) => t.kind(),
         }
     }
 
@@ -36,6 +109,7 @@ impl Transport for TransportType {
             TransportType::CH341(t) => t.port(),
             TransportType::SocketCAN(t) => t.port(),
             TransportType::Stub(t) => t.port(),
+            TransportType::Mock(t) => t.port(),
         }
     }
 
@@ -44,6 +118,7 @@ impl Transport for TransportType {
             TransportType::CH341(t) => t.send(id, data),
             TransportType::SocketCAN(t) => t.send(id, data),
             TransportType::Stub(t) => t.send(id, data),
+            TransportType::Mock(t) => t.send(id, data),
         }
     }
 
@@ -52,6 +127,34 @@ impl Transport for TransportType {
             TransportType::CH341(t) => t.recv(),
             TransportType::SocketCAN(t) => t.recv(),
             TransportType::Stub(t) => t.recv(),
+            TransportType::Mock(t) => t.recv(),
+        }
+    }
+
+    fn flush_input(&mut self) -> FlushFuture<'_> {
+        match self {
+            TransportType::CH341(t) => t.flush_input(),
+            TransportType::SocketCAN(t) => t.flush_input(),
+            TransportType::Stub(t) => t.flush_input(),
+            TransportType::Mock(t) => t.flush_input(),
+        }
+    }
+
+    fn rx_stats(&self) -> RxStats {
+        match self {
+            TransportType::CH341(t) => t.rx_stats(),
+            TransportType::SocketCAN(t) => t.rx_stats(),
+            TransportType::Stub(t) => t.rx_stats(),
+            TransportType::Mock(t) => t.rx_stats(),
+        }
+    }
+
+    fn full_duplex(&self) -> bool {
+        match self {
+            TransportType::CH341(t) => t.full_duplex(),
+            TransportType::SocketCAN(t) => t.full_duplex(),
+            TransportType::Stub(t) => t.full_duplex(),
+            TransportType::Mock(t) => t.full_duplex(),
         }
     }
 }
@@ -61,15 +164,35 @@ pub trait Transport {
     fn port(&self) -> String;
     fn send<'a>(&'a mut self, id: u32, data: &'a [u8]) -> SendFuture<'a>;
     fn recv(&mut self) -> RecvFuture<'_>;
+    /// Discards any received bytes that have not been read yet
+    fn flush_input(&mut self) -> FlushFuture<'_>;
+    /// Framing errors since the transport was opened
+    fn rx_stats(&self) -> RxStats {
+        RxStats::default()
+    }
+    /// Whether `send` can run concurrently with a pending `recv` on a clone
+    /// of the transport without waiting for it
+    fn full_duplex(&self) -> bool {
+        false
+    }
 }
 
 pub struct CH341Transport {
     ser: Arc<TokioMutex<SerialStream>>,
+    decoder: Arc<TokioMutex<CH341Decoder>>,
+    // Copy of the decoder's counters, readable while a receive holds the
+    // decoder
+    rx_stats: Arc<Mutex<RxStats>>,
+    // Set by `flush_input` while a receive holds the decoder, for that
+    // receive to carry out the flush
+    flush_pending: Arc<AtomicBool>,
     port_name: String,
 }
 
 pub struct SocketCanTransport {
-    socket: Arc<TokioMutex<CanSocket>>,
+    // Reads and writes take the socket by shared reference, so a pending
+    // read does not block a write
+    socket: Arc<CanSocket>,
     interface_name: String,
 }
 
@@ -79,9 +202,21 @@ pub struct StubTransport {
 
 impl CH341Transport {
     pub async fn new(port_name: String) -> Result<Self, Error> {
-        let ser = tokio_serial::new(&port_name, 921600).open_native_async()?;
+        Self::with_config(port_name, SerialConfig::default()).await
+    }
+
+    pub async fn with_config(port_name: String, config: SerialConfig) -> Result<Self, Error> {
+        let ser = tokio_serial::new(&port_name, config.baud_rate)
+            .flow_control(config.flow_control)
+            .stop_bits(config.stop_bits)
+            .open_native_async()?;
+        // Drop stale bytes left over from a previous session
+        ser.clear(ClearBuffer::Input)?;
         Ok(Self {
             ser: Arc::new(TokioMutex::new(ser)),
+            decoder: Arc::new(TokioMutex::new(CH341Decoder::new())),
+            rx_stats: Arc::new(Mutex::new(RxStats::default())),
+            flush_pending: Arc::new(AtomicBool::new(false)),
             port_name,
         })
     }
@@ -91,7 +226,7 @@ impl SocketCanTransport {
     pub async fn new(interface_name: String) -> Result<Self, Error> {
         let socket = CanSocket::open(&interface_name)?;
         Ok(Self {
-            socket: Arc::new(TokioMutex::new(socket)),
+            socket: Arc::new(socket),
             interface_name,
         })
     }
@@ -107,14 +242,7 @@ impl Transport for CH341Transport {
     fn send<'a>(&'a mut self, id: u32, data: &'a [u8]) -> SendFuture<'a> {
         let ser = self.ser.clone();
         Box::pin(async move {
-            let mut pkt = Vec::new();
-            pkt.extend_from_slice(b"AT");
-            let addr = (id << 3) | 0x4;
-            pkt.extend_from_slice(&addr.to_be_bytes());
-            pkt.push(data.len() as u8);
-            pkt.extend_from_slice(data);
-            pkt.extend_from_slice(b"\r\n");
-
+            let pkt = ch341_frame(id, data);
             {
                 let mut ser = ser.lock().await;
                 ser.write_all(&pkt).await?;
@@ -126,33 +254,32 @@ impl Transport for CH341Transport {
 
     fn recv(&mut self) -> RecvFuture<'_> {
         let ser = self.ser.clone();
+        let decoder = self.decoder.clone();
+        let rx_stats = self.rx_stats.clone();
+        let flush_pending = self.flush_pending.clone();
+        let port_name = self.port_name.clone();
         Box::pin(async move {
-            let mut buf = vec![0; 1024];
-            let mut pos = 0;
-
-            loop {
-                let n = {
-                    let mut ser = ser.lock().await;
-                    ser.read(&mut buf[pos..]).await?
-                };
-
-                if n == 0 {
-                    return Err(eyre::eyre!("EOF"));
-                }
-                pos += n;
-
-                for i in 0..pos.saturating_sub(7) {
-                    if buf[i] == b'A' && buf[i + 1] == b'T' {
-                        if let Ok((id, data, _msg_len)) = parse_message(&buf[i..pos]) {
-                            return Ok((id, data));
-                        }
-                    }
-                }
+            let mut decoder = decoder.lock().await;
+            recv_frame(&ser, &mut decoder, &rx_stats, &flush_pending, &port_name).await
+        })
+    }
 
-                if pos >= buf.len() - 8 {
-                    return Err(eyre::eyre!("Buffer full without finding valid message"));
+    // A pending receive holds the decoder and the port until bytes arrive,
+    // possibly never on a silent bus, so rather than wait for it the flush is
+    // left to that receive
+    fn flush_input(&mut self) -> FlushFuture<'_> {
+        let ser = self.ser.clone();
+        let decoder = self.decoder.clone();
+        let flush_pending = self.flush_pending.clone();
+        Box::pin(async move {
+            match decoder.try_lock() {
+                Ok(mut decoder) => {
+                    ser.lock().await.clear_input()?;
+                    decoder.clear();
                 }
+                Err(_) => flush_pending.store(true, Ordering::Release),
             }
+            Ok(())
         })
     }
 
@@ -160,38 +287,246 @@ impl Transport for CH341Transport {
         "CH341"
     }
 
+    fn rx_stats(&self) -> RxStats {
+        *self.rx_stats.lock().unwrap()
+    }
+
     fn port(&self) -> String {
         self.port_name.clone()
     }
 }
 
+/// Size of the CH341 receive buffer
+const CH341_RX_BUFFER_SIZE: usize = 1024;
+
+/// Consecutive empty reads after which the CH341 port is considered unplugged
+const CH341_MAX_EMPTY_READS: u32 = 10;
+
+// Wraps a CAN frame for the CH341 adapter: AT, the ID shifted left by 3 with
+// the extended frame bit set, the length byte, the data and \r\n
+pub(crate) fn ch341_frame(id: u32, data: &[u8]) -> Vec<u8> {
+    let mut pkt = Vec::with_capacity(11 + data.len());
+    pkt.extend_from_slice(b"AT");
+    let addr = (id << 3) | 0x4;
+    pkt.extend_from_slice(&addr.to_be_bytes());
+    pkt.push(data.len() as u8);
+    pkt.extend_from_slice(data);
+    pkt.extend_from_slice(b"\r\n");
+    pkt
+}
+
+// The serial side of a CH341 adapter as the receive path uses it, so the
+// path can run against a scripted port
+trait CH341Port: AsyncRead + Unpin + Send {
+    fn clear_input(&self) -> Result<(), Error>;
+}
+
+impl CH341Port for SerialStream {
+    fn clear_input(&self) -> Result<(), Error> {
+        self.clear(ClearBuffer::Input)?;
+        Ok(())
+    }
+}
+
+// Reads from the port into the decoder until it yields a frame
+async fn recv_frame<P: CH341Port>(
+    ser: &TokioMutex<P>,
+    decoder: &mut CH341Decoder,
+    rx_stats: &Mutex<RxStats>,
+    flush_pending: &AtomicBool,
+    port_name: &str,
+) -> RecvResult {
+    let mut empty_reads = 0;
+    let mut resynced = false;
+
+    loop {
+        if flush_pending.swap(false, Ordering::AcqRel) {
+            ser.lock().await.clear_input()?;
+            decoder.clear();
+        }
+
+        let packet = decoder
+            .next_frame()
+            .map(|(id, data)| CanPacket::new(id, data));
+        *rx_stats.lock().unwrap() = decoder.rx_stats();
+        if let Some(packet) = packet {
+            return Ok(packet);
+        }
+
+        let Some(spare) = decoder.spare() else {
+            // The decoder dropped a buffer full of bytes that never formed a
+            // frame. Flush the port and retry once, so persistent garbage
+            // still surfaces as an error.
+            *rx_stats.lock().unwrap() = decoder.rx_stats();
+            if resynced {
+                return Err(eyre::eyre!(
+                    "CH341 port {} out of sync, no valid frame after resync",
+                    port_name
+                ));
+            }
+            tracing::warn!("CH341 port {} out of sync, resyncing", port_name);
+            ser.lock().await.clear_input()?;
+            resynced = true;
+            continue;
+        };
+        let n = {
+            let mut ser = ser.lock().await;
+            ser.read(spare).await?
+        };
+        // Bytes read while a flush was requested are stale too
+        if flush_pending.load(Ordering::Acquire) {
+            continue;
+        }
+
+        // An unplugged adapter can leave the port open with every read
+        // returning no data
+        if n == 0 {
+            empty_reads += 1;
+            if empty_reads >= CH341_MAX_EMPTY_READS {
+                return Err(RobstrideError::Disconnected(port_name.to_string()).into());
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(1)).await;
+            continue;
+        }
+        empty_reads = 0;
+        decoder.commit(n);
+    }
+}
+
+/// Reassembles CH341 `AT` frames from the serial byte stream.
+///
+/// The buffer is owned by the transport and reused for every read, and frames
+/// are parsed in place, so the receive path does not allocate per read. Bytes
+/// following a decoded frame are kept for the next call instead of being
+/// dropped.
+pub struct CH341Decoder {
+    buf: Box<[u8; CH341_RX_BUFFER_SIZE]>,
+    start: usize,
+    end: usize,
+    stats: RxStats,
+}
+
+impl CH341Decoder {
+    pub fn new() -> Self {
+        Self {
+            buf: Box::new([0; CH341_RX_BUFFER_SIZE]),
+            start: 0,
+            end: 0,
+            stats: RxStats::default(),
+        }
+    }
+
+    /// Returns the free space to read new bytes into, or `None` if the buffer
+    /// is full of bytes that do not form a frame (the buffer is cleared).
+    pub fn spare(&mut self) -> Option<&mut [u8]> {
+        if self.start > 0 {
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        if self.end == self.buf.len() {
+            self.stats.discarded_bytes += self.end as u64;
+            self.stats.resyncs += 1;
+            self.end = 0;
+            return None;
+        }
+        Some(&mut self.buf[self.end..])
+    }
+
+    /// Framing errors seen so far. Bytes dropped by `clear` are not counted.
+    pub fn rx_stats(&self) -> RxStats {
+        self.stats
+    }
+
+    /// Discards all buffered bytes.
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.end = 0;
+    }
+
+    /// Marks `n` bytes of the spare space as filled.
+    pub fn commit(&mut self, n: usize) {
+        self.end = (self.end + n).min(self.buf.len());
+    }
+
+    /// Decodes the next complete frame, skipping any bytes that cannot start
+    /// a valid frame. The returned data borrows from the internal buffer.
+    pub fn next_frame(&mut self) -> Option<(u32, &[u8])> {
+        loop {
+            let pending = &self.buf[self.start..self.end];
+            let Some(offset) = pending.windows(2).position(|w| w == b"AT") else {
+                // Keep a trailing 'A' which may be the first half of a prefix
+                let keep = pending.last() == Some(&b'A');
+                let start = self.end - keep as usize;
+                self.stats.discarded_bytes += (start - self.start) as u64;
+                self.start = start;
+                return None;
+            };
+            self.start += offset;
+            self.stats.discarded_bytes += offset as u64;
+
+            match parse_message(&self.buf[self.start..self.end]) {
+                Ok((id, data, msg_len)) => {
+                    // Data follows the AT prefix, ID and length byte
+                    let data_start = self.start + 7;
+                    let data_end = data_start + data.len();
+                    self.start += msg_len;
+                    return Some((id, &self.buf[data_start..data_end]));
+                }
+                Err(ParseError::Incomplete) => return None,
+                Err(ParseError::Invalid) => {
+                    self.stats.invalid_frames += 1;
+                    self.stats.discarded_bytes += 1;
+                    self.start += 1;
+                }
+            }
+        }
+    }
+}
+
+impl Default for CH341Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParseError {
+    /// More bytes are needed to decide
+    Incomplete,
+    /// The bytes do not form a valid message
+    Invalid,
+}
+
 // Helper function to parse a single message
-fn parse_message(buf: &[u8]) -> Result<(u32, Vec<u8>, usize), Error> {
-    // Ensure we have at least the minimum length for a valid packet
-    // AT + 4 bytes ID + 1 byte length + \r\n = 8 bytes minimum
-    if buf.len() < 8 {
-        return Err(eyre::eyre!("Buffer too short"));
+fn parse_message(buf: &[u8]) -> Result<(u32, &[u8], usize), ParseError> {
+    // Ensure we have at least the header: AT + 4 bytes ID + 1 byte length
+    if buf.len() < 7 {
+        return Err(ParseError::Incomplete);
     }
 
     // Verify AT prefix
     if buf[0] != b'A' || buf[1] != b'T' {
-        return Err(eyre::eyre!("Invalid AT prefix"));
+        return Err(ParseError::Invalid);
     }
 
-    // Get data length
+    // Get data length, a CAN frame carries at most 8 bytes
     let data_len = buf[6] as usize;
+    if data_len > 8 {
+        return Err(ParseError::Invalid);
+    }
 
     // Calculate total message length
     let total_len = 7 + data_len + 2; // AT + ID + len + data + \r\n
 
     // Ensure we have enough bytes for the complete packet
     if buf.len() < total_len {
-        return Err(eyre::eyre!("Incomplete message"));
+        return Err(ParseError::Incomplete);
     }
 
     // Check \r\n termination
     if buf[total_len - 2] != b'\r' || buf[total_len - 1] != b'\n' {
-        return Err(eyre::eyre!("Invalid message termination"));
+        return Err(ParseError::Invalid);
     }
 
     // Extract CAN ID (4 bytes, big endian)
@@ -202,10 +537,7 @@ fn parse_message(buf: &[u8]) -> Result<(u32, Vec<u8>, usize), Error> {
     // Undo the encoding: shift right by 3 and clear the highest 3 bits
     let id = (raw_id >> 3) & 0x1FFF_FFFF; // Mask to 29 bits (extended CAN ID)
 
-    // Extract data
-    let data = buf[7..7 + data_len].to_vec();
-
-    Ok((id, data, total_len))
+    Ok((id, &buf[7..7 + data_len], total_len))
 }
 
 impl Transport for SocketCanTransport {
@@ -217,10 +549,7 @@ impl Transport for SocketCanTransport {
             let msg = socketcan::CanFrame::new(extended_id, data)
                 .ok_or_else(|| eyre::eyre!("Failed to create CAN frame"))?;
 
-            {
-                let socket = socket.lock().await;
-                socket.write_frame(&msg).await?;
-            }
+            socket.write_frame(&msg).await?;
             Ok(())
         })
     }
@@ -228,19 +557,25 @@ impl Transport for SocketCanTransport {
     fn recv(&mut self) -> RecvFuture<'_> {
         let socket = self.socket.clone();
         Box::pin(async move {
-            let frame = {
-                let socket = socket.lock().await;
-                socket.read_frame().await?
-            };
+            let frame = socket.read_frame().await?;
 
             let id = match frame.id() {
                 socketcan::Id::Standard(id) => id.as_raw() as u32,
                 socketcan::Id::Extended(id) => id.as_raw(),
             };
-            Ok((id, frame.data().to_vec()))
+            Ok(CanPacket::new(id, frame.data()))
         })
     }
 
+    // Frames are delivered whole by the kernel, so there is nothing to resync
+    fn flush_input(&mut self) -> FlushFuture<'_> {
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn full_duplex(&self) -> bool {
+        true
+    }
+
     fn kind(&self) -> &'static str {
         "SocketCAN"
     }
@@ -259,6 +594,10 @@ impl Transport for StubTransport {
         "Stub"
     }
 
+    fn flush_input(&mut self) -> FlushFuture<'_> {
+        Box::pin(async move { Ok(()) })
+    }
+
     fn send<'a>(&'a mut self, id: u32, data: &'a [u8]) -> SendFuture<'a> {
         tracing::debug!("StubTransport::send: id={:04x}, data={:02x?}", id, data);
         Box::pin(async move { Ok(()) })
@@ -266,19 +605,95 @@ impl Transport for StubTransport {
 
     fn recv(&mut self) -> RecvFuture<'_> {
         let id = 0x2000100;
-        let data = vec![0x7f, 0xfe, 0x80, 0x73, 0x7f, 0xff, 0x01, 0x18];
+        let data = [0x7f, 0xfe, 0x80, 0x73, 0x7f, 0xff, 0x01, 0x18];
         // tracing::debug!("StubTransport::recv: id={:04x}, data={:02x?}", id, data);
         Box::pin(async move {
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-            Ok((id, data))
+            Ok(CanPacket::new(id, &data))
+        })
+    }
+}
+
+/// In-memory transport for running the supervisor without hardware, e.g. in
+/// tests. `recv` returns the frames queued with `push_frame` in order and
+/// waits while the queue is empty; `send` records every frame. Clones share
+/// the queue and the record, so keep one to script and inspect the traffic.
+#[derive(Clone, Default)]
+pub struct MockTransport {
+    incoming: Arc<Mutex<VecDeque<CanPacket>>>,
+    arrived: Arc<Notify>,
+    sent: Arc<Mutex<Vec<CanPacket>>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a frame for `recv` to return.
+    pub fn push_frame(&self, id: u32, data: &[u8]) {
+        self.incoming
+            .lock()
+            .unwrap()
+            .push_back(CanPacket::new(id, data));
+        self.arrived.notify_one();
+    }
+
+    /// Frames sent so far, oldest first.
+    pub fn sent(&self) -> Vec<CanPacket> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// Forgets the frames sent so far.
+    pub fn clear_sent(&self) {
+        self.sent.lock().unwrap().clear();
+    }
+}
+
+impl Transport for MockTransport {
+    fn kind(&self) -> &'static str {
+        "Mock"
+    }
+
+    fn port(&self) -> String {
+        String::from("mock")
+    }
+
+    fn send<'a>(&'a mut self, id: u32, data: &'a [u8]) -> SendFuture<'a> {
+        self.sent.lock().unwrap().push(CanPacket::new(id, data));
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn recv(&mut self) -> RecvFuture<'_> {
+        let incoming = self.incoming.clone();
+        let arrived = self.arrived.clone();
+        Box::pin(async move {
+            loop {
+                if let Some(packet) = incoming.lock().unwrap().pop_front() {
+                    return Ok(packet);
+                }
+                arrived.notified().await;
+            }
         })
     }
+
+    fn flush_input(&mut self) -> FlushFuture<'_> {
+        self.incoming.lock().unwrap().clear();
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn full_duplex(&self) -> bool {
+        true
+    }
 }
 
 impl Clone for CH341Transport {
     fn clone(&self) -> Self {
         Self {
             ser: self.ser.clone(),
+            decoder: self.decoder.clone(),
+            rx_stats: self.rx_stats.clone(),
+            flush_pending: self.flush_pending.clone(),
             port_name: self.port_name.clone(),
         }
     }
@@ -300,3 +715,135 @@ impl Clone for StubTransport {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::ReadBuf;
+
+    // Serial port that hands out one scripted chunk per read, and empty reads
+    // once the script runs out, like an unplugged adapter
+    struct ScriptedPort {
+        reads: VecDeque<Vec<u8>>,
+        read_count: usize,
+    }
+
+    impl ScriptedPort {
+        fn new(reads: impl IntoIterator<Item = Vec<u8>>) -> TokioMutex<Self> {
+            TokioMutex::new(Self {
+                reads: reads.into_iter().collect(),
+                read_count: 0,
+            })
+        }
+    }
+
+    impl AsyncRead for ScriptedPort {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            self.read_count += 1;
+            if let Some(chunk) = self.reads.pop_front() {
+                buf.put_slice(&chunk);
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl CH341Port for ScriptedPort {
+        fn clear_input(&self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    async fn recv(port: &TokioMutex<ScriptedPort>) -> RecvResult {
+        recv_frame(
+            port,
+            &mut CH341Decoder::new(),
+            &Mutex::new(RxStats::default()),
+            &AtomicBool::new(false),
+            "scripted",
+        )
+        .await
+    }
+
+    // Copies `bytes` into the decoder as if one read returned them
+    fn feed(decoder: &mut CH341Decoder, bytes: &[u8]) {
+        decoder.spare().unwrap()[..bytes.len()].copy_from_slice(bytes);
+        decoder.commit(bytes.len());
+    }
+
+    // CH341 frames are AT + 4 byte ID + length byte + data + \r\n, so frames
+    // with short payloads must be split on their own length
+    #[test]
+    fn short_payload_frames_are_split_on_their_length() {
+        let frames: [(u32, &[u8]); 3] = [
+            (0x0200_fd01, &[0x01, 0x02, 0x03, 0x04]),
+            (
+                0x0200_fd02,
+                &[0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17],
+            ),
+            (0x1100_fd03, &[0xaa, 0xbb]),
+        ];
+        // Leading noise and a frame with a bad terminator are skipped and
+        // counted
+        let mut stream = vec![0x00, 0x13, 0x37];
+        stream.extend_from_slice(b"AT\x00\x00\x00\x00\x01\xffXX");
+        for (id, data) in frames {
+            stream.extend(ch341_frame(id, data));
+        }
+
+        let mut decoder = CH341Decoder::new();
+        feed(&mut decoder, &stream);
+        for (id, data) in frames {
+            let (decoded_id, decoded_data) = decoder.next_frame().unwrap();
+            assert_eq!(decoded_id, id);
+            assert_eq!(decoded_data, data);
+        }
+        assert!(decoder.next_frame().is_none());
+        let stats = decoder.rx_stats();
+        assert_eq!(stats.invalid_frames, 1);
+        assert_eq!(stats.discarded_bytes, 3 + 10);
+    }
+
+    // A junk byte and a frame split across two reads: the partial frame is
+    // kept until the rest arrives
+    #[test]
+    fn frame_split_across_reads_is_kept_until_complete() {
+        let mut stream = vec![0x55];
+        stream.extend(ch341_frame(0x0200_fd01, &[0x21; 8]));
+        let (first, second) = stream.split_at(9);
+
+        let mut decoder = CH341Decoder::new();
+        feed(&mut decoder, first);
+        assert!(decoder.next_frame().is_none());
+        feed(&mut decoder, second);
+        let (id, data) = decoder.next_frame().unwrap();
+        assert_eq!(id, 0x0200_fd01);
+        assert_eq!(data, [0x21; 8]);
+        assert_eq!(decoder.rx_stats().discarded_bytes, 1);
+    }
+
+    #[tokio::test]
+    async fn empty_reads_report_disconnect() {
+        let port = ScriptedPort::new([]);
+        let error = recv(&port).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<RobstrideError>(),
+            Some(&RobstrideError::Disconnected("scripted".to_string()))
+        );
+        assert_eq!(port.lock().await.read_count, CH341_MAX_EMPTY_READS as usize);
+    }
+
+    #[tokio::test]
+    async fn frame_after_a_few_empty_reads_is_received() {
+        let data = [0x7f, 0xfe, 0x80, 0x73, 0x7f, 0xff, 0x01, 0x18];
+        let empty = (1..CH341_MAX_EMPTY_READS).map(|_| Vec::new());
+        let port = ScriptedPort::new(empty.chain([ch341_frame(0x0200_fd01, &data)]));
+        let packet = recv(&port).await.unwrap();
+        assert_eq!(packet, CanPacket::new(0x0200_fd01, &data));
+    }
+}